@@ -0,0 +1,169 @@
+//! Encryption-at-rest for the file repository.
+
+// Copyright (c) 2009,2024 Testrepository Contributors
+//
+// Licensed under either the Apache License, Version 2.0 or the BSD 3-clause
+// license at the users choice. A copy of both licenses are available in the
+// project source as Apache-2.0 and BSD. You may not use this file except in
+// compliance with one of these two licences.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under these licenses is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// license you chose for the specific language governing permissions and
+// limitations under that license.
+
+use aead::{Aead, KeyInit};
+use argon2::{Algorithm, Argon2, Params, Version};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Eyrify, Result};
+
+/// The cipher, if any, used to encrypt a repository's stream files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Cipher {
+    /// Stream files are stored as plain (unauthenticated, unencrypted) JSON.
+    None,
+    /// AES-256 in GCM mode.
+    Aes256Gcm,
+    /// ChaCha20-Poly1305.
+    ChaCha20Poly1305,
+}
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+
+/// The on-disk record of how a repository is encrypted: the cipher, the KDF
+/// salt, and the KDF cost parameters, so that a later open can re-derive the
+/// same key from the user's password.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptionHeader {
+    pub cipher: Cipher,
+    pub salt: Vec<u8>,
+    /// Argon2 `t_cost` (number of iterations).
+    pub ops_cost: u32,
+    /// Argon2 `m_cost`, in KiB.
+    pub mem_cost: u32,
+}
+
+impl EncryptionHeader {
+    /// Generate a fresh header for `cipher`, with a random salt and sensible
+    /// default KDF cost parameters.
+    pub fn generate(cipher: Cipher) -> Self {
+        let mut salt = vec![0u8; SALT_LEN];
+        rand::thread_rng().fill_bytes(&mut salt);
+        Self {
+            cipher,
+            salt,
+            // 3 passes over 64MiB: expensive enough to slow down offline
+            // guessing, cheap enough for an interactive `testr` command.
+            ops_cost: 3,
+            mem_cost: 64 * 1024,
+        }
+    }
+
+    fn derive_key(&self, password: &str) -> Result<[u8; KEY_LEN]> {
+        let params = Params::new(self.mem_cost, self.ops_cost, 1, Some(KEY_LEN))
+            .map_err(|e| eyre::eyre!("Invalid KDF parameters: {}", e))?;
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+        let mut key = [0u8; KEY_LEN];
+        argon2
+            .hash_password_into(password.as_bytes(), &self.salt, &mut key)
+            .map_err(|e| eyre::eyre!("Key derivation failed: {}", e))?;
+        Ok(key)
+    }
+
+    /// Derive the [`Encryptor`] for this header from `password`.
+    pub fn encryptor(&self, password: &str) -> Result<Encryptor> {
+        let key = self.derive_key(password)?;
+        Encryptor::new(self.cipher, &key)
+    }
+}
+
+/// Encrypts and decrypts stream files once a key has been derived.
+#[derive(Clone)]
+pub enum Encryptor {
+    /// No encryption; stream files are stored as-is.
+    None,
+    Aes256Gcm(Box<aes_gcm::Aes256Gcm>),
+    ChaCha20Poly1305(Box<chacha20poly1305::ChaCha20Poly1305>),
+}
+
+impl std::fmt::Debug for Encryptor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Encryptor::None => "None",
+            Encryptor::Aes256Gcm(_) => "Aes256Gcm",
+            Encryptor::ChaCha20Poly1305(_) => "ChaCha20Poly1305",
+        };
+        f.debug_tuple("Encryptor").field(&name).finish()
+    }
+}
+
+impl Encryptor {
+    fn new(cipher: Cipher, key: &[u8; KEY_LEN]) -> Result<Self> {
+        Ok(match cipher {
+            Cipher::None => Encryptor::None,
+            Cipher::Aes256Gcm => Encryptor::Aes256Gcm(Box::new(
+                aes_gcm::Aes256Gcm::new_from_slice(key).eyre()?,
+            )),
+            Cipher::ChaCha20Poly1305 => Encryptor::ChaCha20Poly1305(Box::new(
+                chacha20poly1305::ChaCha20Poly1305::new_from_slice(key).eyre()?,
+            )),
+        })
+    }
+
+    /// Encrypt `plaintext`, returning a nonce-prefixed authenticated blob.
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Encryptor::None => Ok(plaintext.to_vec()),
+            Encryptor::Aes256Gcm(cipher) => {
+                let mut nonce = [0u8; NONCE_LEN];
+                rand::thread_rng().fill_bytes(&mut nonce);
+                let mut out = cipher
+                    .encrypt(aes_gcm::Nonce::from_slice(&nonce), plaintext)
+                    .map_err(|_| eyre::eyre!("Encryption failed"))?;
+                let mut blob = nonce.to_vec();
+                blob.append(&mut out);
+                Ok(blob)
+            }
+            Encryptor::ChaCha20Poly1305(cipher) => {
+                let mut nonce = [0u8; NONCE_LEN];
+                rand::thread_rng().fill_bytes(&mut nonce);
+                let mut out = cipher
+                    .encrypt(chacha20poly1305::Nonce::from_slice(&nonce), plaintext)
+                    .map_err(|_| eyre::eyre!("Encryption failed"))?;
+                let mut blob = nonce.to_vec();
+                blob.append(&mut out);
+                Ok(blob)
+            }
+        }
+    }
+
+    /// Inverse of [`Encryptor::encrypt`].
+    pub fn decrypt(&self, blob: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Encryptor::None => Ok(blob.to_vec()),
+            Encryptor::Aes256Gcm(cipher) => {
+                if blob.len() < NONCE_LEN {
+                    Err(eyre::eyre!("Encrypted stream is truncated"))?
+                }
+                let (nonce, ciphertext) = blob.split_at(NONCE_LEN);
+                cipher
+                    .decrypt(aes_gcm::Nonce::from_slice(nonce), ciphertext)
+                    .map_err(|_| eyre::eyre!("Decryption failed: wrong password or corrupt data?").into())
+            }
+            Encryptor::ChaCha20Poly1305(cipher) => {
+                if blob.len() < NONCE_LEN {
+                    Err(eyre::eyre!("Encrypted stream is truncated"))?
+                }
+                let (nonce, ciphertext) = blob.split_at(NONCE_LEN);
+                cipher
+                    .decrypt(chacha20poly1305::Nonce::from_slice(nonce), ciphertext)
+                    .map_err(|_| eyre::eyre!("Decryption failed: wrong password or corrupt data?").into())
+            }
+        }
+    }
+}
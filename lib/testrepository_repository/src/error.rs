@@ -13,6 +13,8 @@
 // license you chose for the specific language governing permissions and
 // limitations under that license.
 
+use std::{fmt, io, path::PathBuf};
+
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -21,6 +23,72 @@ pub enum Error {
     /// Errors that shouldn't need matching on
     #[error("{0}")]
     Eyre(#[from] eyre::Report),
+    /// A filesystem operation failed; see [`IoOpError`] for the operation,
+    /// path, and (for `*_at` calls) directory it was relative to.
+    #[error(transparent)]
+    Io(#[from] IoOpError),
+}
+
+/// A filesystem operation performed by [`crate::io`], named for inclusion in
+/// an [`IoOpError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum IoOp {
+    OpenDir,
+    MkdirAt,
+    OpenDirAt,
+    OpenAt,
+    UnlinkAt,
+    RmdirAt,
+    RenameAt,
+    SymlinkAt,
+    ReadDirAt,
+    ReadlinkAt,
+}
+
+impl fmt::Display for IoOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            IoOp::OpenDir => "open_dir",
+            IoOp::MkdirAt => "mkdir_at",
+            IoOp::OpenDirAt => "open_dir_at",
+            IoOp::OpenAt => "open_at",
+            IoOp::UnlinkAt => "unlink_at",
+            IoOp::RmdirAt => "rmdir_at",
+            IoOp::RenameAt => "rename_at",
+            IoOp::SymlinkAt => "symlink_at",
+            IoOp::ReadDirAt => "read_dir_at",
+            IoOp::ReadlinkAt => "readlink_at",
+        })
+    }
+}
+
+/// An IO failure that records which operation and path it happened on (and,
+/// for `*_at` calls, the directory it was relative to), so error messages
+/// read like `mkdir_at("subdir") relative to "/base": File exists` instead of
+/// a bare OS error.
+#[derive(Debug)]
+pub struct IoOpError {
+    pub op: IoOp,
+    pub path: PathBuf,
+    pub relative_to: Option<String>,
+    pub source: io::Error,
+}
+
+impl fmt::Display for IoOpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}({:?})", self.op, self.path)?;
+        if let Some(relative_to) = &self.relative_to {
+            write!(f, " relative to {}", relative_to)?;
+        }
+        write!(f, ": {}", self.source)
+    }
+}
+
+impl std::error::Error for IoOpError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -13,27 +13,49 @@
 // license you chose for the specific language governing permissions and
 // limitations under that license.
 
-use std::path::{Path, PathBuf};
+#[cfg(not(windows))]
+use std::os::unix::io::AsRawFd;
+#[cfg(windows)]
+use std::os::windows::io::AsRawHandle;
+#[cfg(windows)]
+use std::os::windows::io::{FromRawHandle, IntoRawHandle};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File as StdFile,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 
 use async_trait::async_trait;
 use eyre::eyre;
 use fs_at::OpenOptionsWriteMode;
 use futures::future::TryFutureExt;
-use tokio::io::{AsyncReadExt, AsyncWriteExt as _};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt as _},
+    task,
+};
 use tracing::instrument;
 
 use crate::{
+    encryption::{Cipher, EncryptionHeader, Encryptor},
     error::{Eyrify, Result},
-    io::{self, ArcFile, AsyncOptionOptions, OpenOptions},
+    io::{self, AsyncOptionOptions, NamedDir, OpenOptions},
     repository::Repository,
+    result::TestResult,
+    retention::RetentionPolicy,
+    run::RunWriter,
 };
 
 pub static REPO_DIR: &str = ".testrepository";
 pub static FORMAT_FILE: &str = "format";
 pub static NEXT_STREAM_FILE: &str = "next-stream";
+pub static OLDEST_STREAM_FILE: &str = "oldest-stream";
+pub static ENCRYPTION_FILE: &str = "encryption";
+pub static FAILING_FILE: &str = "failing";
 
 /// Shared version helper functions.
-async fn count(root: &ArcFile) -> Result<usize> {
+async fn read_next_stream(root: &NamedDir) -> Result<usize> {
     let mut stream_content = String::new();
     let stream_content = OpenOptions::default()
         .read(true)
@@ -49,10 +71,238 @@ async fn count(root: &ArcFile) -> Result<usize> {
     stream_content.trim().parse::<usize>().eyre()
 }
 
+/// The id of the oldest stream still present, i.e. the low-water mark left by
+/// [`prune`]. Repositories predating retention support (and v1 repositories,
+/// which don't support pruning) have no such file, so default to zero.
+async fn read_oldest_stream(root: &NamedDir) -> Result<usize> {
+    let mut content = String::new();
+    match OpenOptions::default()
+        .read(true)
+        .open_at(root, OLDEST_STREAM_FILE)
+        .await
+    {
+        Ok(mut f) => {
+            f.read_to_string(&mut content).await.eyre()?;
+            content.trim().parse::<usize>().eyre()
+        }
+        Err(_) => Ok(0),
+    }
+}
+
+/// Number of runs currently stored, i.e. not yet pruned.
+async fn count(root: &NamedDir) -> Result<usize> {
+    Ok(read_next_stream(root).await? - read_oldest_stream(root).await?)
+}
+
+/// Read the results stored in the numbered stream file `id` under `root`,
+/// transparently decrypting it if `root`'s repository is encrypted.
+async fn get_run(root: &NamedDir, id: usize, encryptor: &Encryptor) -> Result<Vec<TestResult>> {
+    let mut content = Vec::new();
+    OpenOptions::default()
+        .read(true)
+        .open_at(root, id.to_string())
+        .await?
+        .read_to_end(&mut content)
+        .await
+        .eyre()?;
+    let plaintext = encryptor.decrypt(&content)?;
+    serde_json::from_slice(&plaintext).eyre()
+}
+
+/// Read the `failing` index: the run each currently-failing test id last
+/// failed in. Repositories that predate this index have no such file, so
+/// default to empty.
+async fn read_failing_index(root: &NamedDir) -> Result<HashMap<String, usize>> {
+    let mut content = String::new();
+    match OpenOptions::default()
+        .read(true)
+        .open_at(root, FAILING_FILE)
+        .await
+    {
+        Ok(mut f) => {
+            f.read_to_string(&mut content).await.eyre()?;
+            serde_json::from_str(&content).eyre()
+        }
+        Err(_) => Ok(HashMap::new()),
+    }
+}
+
+async fn write_failing_index(root: &NamedDir, index: &HashMap<String, usize>) -> Result<()> {
+    let opts = *OpenOptions::default()
+        .create(true)
+        .write(OpenOptionsWriteMode::Write);
+    opts.open_at(root, FAILING_FILE)
+        .await?
+        .write_all(&serde_json::to_vec(index).eyre()?)
+        .await
+        .eyre()
+}
+
+/// Incrementally fold the outcomes of the just-committed run `run_id` into
+/// the `failing` index: a failing result sets (or keeps) the id's entry,
+/// while a passing result clears it, since the newest observation always
+/// wins.
+async fn update_failing_index(root: &NamedDir, run_id: usize, results: &[TestResult]) -> Result<()> {
+    let mut index = read_failing_index(root).await?;
+    for result in results {
+        if result.status.is_failure() {
+            index.insert(result.id.clone(), run_id);
+        } else {
+            index.remove(&result.id);
+        }
+    }
+    write_failing_index(root, &index).await
+}
+
+/// The tests currently considered failing, read back via the `failing`
+/// index rather than replaying every stored run.
+async fn failing(root: &NamedDir, encryptor: &Encryptor) -> Result<Vec<TestResult>> {
+    let index = read_failing_index(root).await?;
+    let mut ids_by_run: HashMap<usize, Vec<&str>> = HashMap::new();
+    for (id, run_id) in &index {
+        ids_by_run.entry(*run_id).or_default().push(id.as_str());
+    }
+    let mut results = Vec::with_capacity(index.len());
+    for (run_id, ids) in ids_by_run {
+        for result in get_run(root, run_id, encryptor).await? {
+            if ids.contains(&result.id.as_str()) {
+                results.push(result);
+            }
+        }
+    }
+    Ok(results)
+}
+
+/// The newest timestamp observed amongst a run's results, used to decide its
+/// age for retention purposes. An empty run is treated as maximally old.
+fn newest_time(run: &[TestResult]) -> SystemTime {
+    run.iter()
+        .map(|r| r.stop_time)
+        .max()
+        .unwrap_or(std::time::UNIX_EPOCH)
+}
+
+/// Delete the numbered stream file `id` under `root`.
+///
+/// `root` is a plain directory handle rather than a path, so this follows the
+/// same borrowed-fd-via-`from_raw_*`/`into_raw_*` dance as
+/// [`OpenOptions::open_blocking_file`] to hand the blocking pool a `std::fs`
+/// view of it without taking ownership.
+async fn delete_stream(root: &NamedDir, id: usize) -> Result<()> {
+    task::spawn_blocking({
+        let name = PathBuf::from(id.to_string());
+        let owned_root = Arc::clone(&root.file);
+        move || {
+            #[cfg(windows)]
+            let std_root = unsafe { StdFile::from_raw_handle(owned_root.as_raw_handle()) };
+            #[cfg(not(windows))]
+            let std_root = unsafe { StdFile::from_raw_fd(owned_root.as_raw_fd()) };
+            let r = fs_at::unlink_at(&std_root, &name, fs_at::UnlinkatFlags::empty());
+            #[cfg(windows)]
+            std_root.into_raw_handle();
+            #[cfg(not(windows))]
+            std_root.into_raw_fd();
+            drop(owned_root);
+            r
+        }
+    })
+    .await
+    .eyre()?
+    .eyre()
+}
+
+/// Record the new low-water mark after pruning.
+async fn write_oldest_stream(root: &NamedDir, oldest: usize) -> Result<()> {
+    let opts = *OpenOptions::default()
+        .create(true)
+        .write(OpenOptionsWriteMode::Write);
+    opts.open_at(root, OLDEST_STREAM_FILE)
+        .await?
+        .write_all(format!("{}\n", oldest).as_bytes())
+        .await
+        .eyre()
+}
+
+/// Prune runs that fall outside `policy`, deleting their stream files while
+/// keeping `next-stream` monotonic so ids are never reused.
+async fn prune(
+    root: &NamedDir,
+    policy: &RetentionPolicy,
+    now: SystemTime,
+    encryptor: &Encryptor,
+) -> Result<usize> {
+    if policy.is_unlimited() {
+        return Ok(0);
+    }
+    let oldest = read_oldest_stream(root).await?;
+    let next = read_next_stream(root).await?;
+    if oldest >= next {
+        return Ok(0);
+    }
+    let mut times = Vec::with_capacity(next - oldest);
+    for id in oldest..next {
+        times.push(newest_time(&get_run(root, id, encryptor).await?));
+    }
+    let prune_n = policy.prune_count(&times, now);
+    for id in oldest..oldest + prune_n {
+        delete_stream(root, id).await?;
+    }
+    if prune_n > 0 {
+        write_oldest_stream(root, oldest + prune_n).await?;
+        // Entries pointing at a now-deleted stream file would otherwise
+        // dangle until their test next runs.
+        let mut index = read_failing_index(root).await?;
+        index.retain(|_, run_id| *run_id >= oldest + prune_n);
+        write_failing_index(root, &index).await?;
+    }
+    Ok(prune_n)
+}
+
+/// Collects the results of a single run before writing them to a new numbered
+/// stream file and bumping `next-stream` on commit.
+#[derive(Debug)]
+pub struct FileRunWriter {
+    root: NamedDir,
+    retention: RetentionPolicy,
+    encryptor: Encryptor,
+    results: Vec<TestResult>,
+}
+
+impl FileRunWriter {
+    pub(crate) fn push(&mut self, result: TestResult) {
+        self.results.push(result);
+    }
+
+    pub(crate) async fn commit(self) -> Result<()> {
+        let id = read_next_stream(&self.root).await?;
+        let content = serde_json::to_vec(&self.results).eyre()?;
+        let blob = self.encryptor.encrypt(&content)?;
+        let opts = *OpenOptions::default()
+            .create_new(true)
+            .write(OpenOptionsWriteMode::Write);
+        opts.open_at(&self.root, id.to_string())
+            .await?
+            .write_all(&blob)
+            .await
+            .eyre()?;
+        let opts = *OpenOptions::default()
+            .create(true)
+            .write(OpenOptionsWriteMode::Write);
+        opts.open_at(&self.root, NEXT_STREAM_FILE)
+            .await?
+            .write_all(format!("{}\n", id + 1).as_bytes())
+            .await
+            .eyre()?;
+        update_failing_index(&self.root, id, &self.results).await?;
+        prune(&self.root, &self.retention, SystemTime::now(), &self.encryptor).await?;
+        Ok(())
+    }
+}
+
 /// File repository compatible with the python Testrepository
 #[derive(Debug)]
 struct TestRepositoryV1Repo {
-    root: ArcFile,
+    root: NamedDir,
 }
 
 #[async_trait]
@@ -60,18 +310,96 @@ impl Repository for TestRepositoryV1Repo {
     async fn count(&self) -> Result<usize> {
         count(&self.root).await
     }
+
+    async fn open_run(&self) -> Result<RunWriter> {
+        // The python-compatible v1 format stores runs as subunit streams,
+        // which this crate does not yet produce or parse.
+        Err(eyre!("Ingesting runs into a v1 repository is not supported").into())
+    }
+
+    async fn get_run(&self, _id: usize) -> Result<Vec<TestResult>> {
+        Err(eyre!("Reading runs from a v1 repository is not supported").into())
+    }
+
+    async fn prune(&self) -> Result<usize> {
+        Err(eyre!("Pruning a v1 repository is not supported").into())
+    }
+
+    async fn failing(&self) -> Result<Vec<TestResult>> {
+        Err(eyre!("Querying failing tests from a v1 repository is not supported").into())
+    }
 }
 
 /// File repository that uses different storage...
 #[derive(Debug)]
 struct TestRepositoryV2Repo {
-    root: ArcFile,
+    root: NamedDir,
+    retention: RetentionPolicy,
+    encryptor: Encryptor,
 }
 #[async_trait]
 impl Repository for TestRepositoryV2Repo {
     async fn count(&self) -> Result<usize> {
         count(&self.root).await
     }
+
+    /// Overridden because ids are `oldest-stream..next-stream`, not
+    /// `0..count`, once anything has ever been pruned.
+    async fn latest_id(&self) -> Result<Option<usize>> {
+        let next = read_next_stream(&self.root).await?;
+        let oldest = read_oldest_stream(&self.root).await?;
+        Ok(if next <= oldest { None } else { Some(next - 1) })
+    }
+
+    async fn open_run(&self) -> Result<RunWriter> {
+        Ok(RunWriter::File(FileRunWriter {
+            root: self.root.clone(),
+            retention: self.retention,
+            encryptor: self.encryptor.clone(),
+            results: Vec::new(),
+        }))
+    }
+
+    async fn get_run(&self, id: usize) -> Result<Vec<TestResult>> {
+        get_run(&self.root, id, &self.encryptor).await
+    }
+
+    /// Overridden because ids are `oldest-stream..next-stream`, not
+    /// `0..count`, once anything has ever been pruned; the default impl's
+    /// `get_run(run_id)` for `run_id < oldest-stream` would hit an
+    /// already-deleted stream file.
+    async fn test_times(
+        &self,
+        ids: &[String],
+        default: Duration,
+    ) -> Result<HashMap<String, Duration>> {
+        let mut times = HashMap::new();
+        let mut remaining: HashSet<&String> = ids.iter().collect();
+        let next = read_next_stream(&self.root).await?;
+        let oldest = read_oldest_stream(&self.root).await?;
+        for run_id in (oldest..next).rev() {
+            if remaining.is_empty() {
+                break;
+            }
+            for result in get_run(&self.root, run_id, &self.encryptor).await? {
+                if remaining.remove(&result.id) {
+                    times.insert(result.id.clone(), result.duration());
+                }
+            }
+        }
+        for id in remaining {
+            times.insert(id.clone(), default);
+        }
+        Ok(times)
+    }
+
+    async fn prune(&self) -> Result<usize> {
+        prune(&self.root, &self.retention, SystemTime::now(), &self.encryptor).await
+    }
+
+    async fn failing(&self) -> Result<Vec<TestResult>> {
+        failing(&self.root, &self.encryptor).await
+    }
 }
 
 /// File repository version layer (could be a type parameter or a dyn instead...)
@@ -89,6 +417,52 @@ impl Repository for FileRepositoryVersion {
             FileRepositoryVersion::V2(repo) => repo.count().await,
         }
     }
+
+    async fn latest_id(&self) -> Result<Option<usize>> {
+        match self {
+            FileRepositoryVersion::V1(repo) => repo.latest_id().await,
+            FileRepositoryVersion::V2(repo) => repo.latest_id().await,
+        }
+    }
+
+    async fn open_run(&self) -> Result<RunWriter> {
+        match self {
+            FileRepositoryVersion::V1(repo) => repo.open_run().await,
+            FileRepositoryVersion::V2(repo) => repo.open_run().await,
+        }
+    }
+
+    async fn get_run(&self, id: usize) -> Result<Vec<TestResult>> {
+        match self {
+            FileRepositoryVersion::V1(repo) => repo.get_run(id).await,
+            FileRepositoryVersion::V2(repo) => repo.get_run(id).await,
+        }
+    }
+
+    async fn prune(&self) -> Result<usize> {
+        match self {
+            FileRepositoryVersion::V1(repo) => repo.prune().await,
+            FileRepositoryVersion::V2(repo) => repo.prune().await,
+        }
+    }
+
+    async fn failing(&self) -> Result<Vec<TestResult>> {
+        match self {
+            FileRepositoryVersion::V1(repo) => repo.failing().await,
+            FileRepositoryVersion::V2(repo) => repo.failing().await,
+        }
+    }
+
+    async fn test_times(
+        &self,
+        ids: &[String],
+        default: Duration,
+    ) -> Result<HashMap<String, Duration>> {
+        match self {
+            FileRepositoryVersion::V1(repo) => repo.test_times(ids, default).await,
+            FileRepositoryVersion::V2(repo) => repo.test_times(ids, default).await,
+        }
+    }
 }
 
 impl PartialEq for FileRepositoryVersion {
@@ -106,6 +480,7 @@ impl PartialEq for FileRepositoryVersion {
 pub struct File {
     engine: FileRepositoryVersion,
     path: PathBuf,
+    retention: RetentionPolicy,
 }
 
 impl File {
@@ -113,19 +488,42 @@ impl File {
     /// at path.
     #[instrument(ret, err)]
     pub async fn new(path: &Path) -> Result<Self> {
+        Self::open(path, None).await
+    }
+
+    /// Create a new File repository instance reading from a repository
+    /// located at path, supplying `password` to derive the decryption key if
+    /// the repository is encrypted.
+    #[instrument(ret, err, skip(password))]
+    pub async fn new_with_password(path: &Path, password: &str) -> Result<Self> {
+        Self::open(path, Some(password)).await
+    }
+
+    async fn open(path: &Path, password: Option<&str>) -> Result<Self> {
         tracing::debug!("Opening repository at '{}'", path.display());
         let base = io::open_dir(path).await?;
         let root = OpenOptions::default()
             .read(true)
             .open_dir_at(&base, REPO_DIR)
             .await?;
-        let engine = Self::validate_format(&root).await?;
+        let retention = RetentionPolicy::default();
+        let engine = Self::validate_format(&root, retention, password).await?;
         Ok(Self {
             engine,
             path: path.to_owned(),
+            retention,
         })
     }
 
+    /// Apply a retention policy, pruning old runs as new ones are committed.
+    pub fn with_retention(mut self, retention: RetentionPolicy) -> Self {
+        self.retention = retention;
+        if let FileRepositoryVersion::V2(repo) = &mut self.engine {
+            repo.retention = retention;
+        }
+        self
+    }
+
     /// Initialize a python testr compatible repository at the given path
     #[instrument(ret, err)]
     #[deprecated(since = "0.1.0", note = "Use initialize instead")]
@@ -158,10 +556,12 @@ impl File {
             .await
             .eyre()?;
 
-        let engine = Self::validate_format(&root).await?;
+        let retention = RetentionPolicy::default();
+        let engine = Self::validate_format(&root, retention, None).await?;
         Ok(Self {
             engine,
             path: path.canonicalize().eyre()?,
+            retention,
         })
     }
 
@@ -195,11 +595,75 @@ impl File {
             .write_all(b"0\n")
             .await
             .eyre()?;
+        opts.open_at(&root, OLDEST_STREAM_FILE)
+            .await?
+            .write_all(b"0\n")
+            .await
+            .eyre()?;
+
+        let retention = RetentionPolicy::default();
+        let engine = Self::validate_format(&root, retention, None).await?;
+        Ok(Self {
+            engine,
+            path: path.canonicalize().eyre()?,
+            retention,
+        })
+    }
+
+    /// Initialize a rust testr compatible repository at the given path, with
+    /// its stream files encrypted under `cipher` using a key derived from
+    /// `password`.
+    #[instrument(ret, err, skip(password))]
+    pub async fn initialize_v2_with_encryption(
+        path: &Path,
+        cipher: Cipher,
+        password: &str,
+    ) -> Result<Self> {
+        let base = io::open_dir(path).await?;
+        if OpenOptions::default()
+            .read(true)
+            .open_dir_at(&base, REPO_DIR)
+            .await
+            .is_ok()
+        {
+            Err(eyre!(
+                ".testrepository already exists at '{}'",
+                path.display()
+            ))?
+        }
+
+        let root = OpenOptions::default().mkdir_at(&base, REPO_DIR).await?;
+        let opts = *OpenOptions::default()
+            .create_new(true)
+            .write(OpenOptionsWriteMode::Write);
+        opts.open_at(&root, FORMAT_FILE)
+            .await?
+            .write_all(b"2\n")
+            .await
+            .eyre()?;
+        opts.open_at(&root, NEXT_STREAM_FILE)
+            .await?
+            .write_all(b"0\n")
+            .await
+            .eyre()?;
+        opts.open_at(&root, OLDEST_STREAM_FILE)
+            .await?
+            .write_all(b"0\n")
+            .await
+            .eyre()?;
+        let header = EncryptionHeader::generate(cipher);
+        opts.open_at(&root, ENCRYPTION_FILE)
+            .await?
+            .write_all(&serde_json::to_vec(&header).eyre()?)
+            .await
+            .eyre()?;
 
-        let engine = Self::validate_format(&root).await?;
+        let retention = RetentionPolicy::default();
+        let engine = Self::validate_format(&root, retention, Some(password)).await?;
         Ok(Self {
             engine,
             path: path.canonicalize().eyre()?,
+            retention,
         })
     }
 
@@ -208,8 +672,15 @@ impl File {
     /// ## Arguments
     ///
     /// * `root` - Open handle on the `.testrepository` directory.
-    #[instrument(ret, err)]
-    async fn validate_format(root: &ArcFile) -> Result<FileRepositoryVersion> {
+    /// * `retention` - Retention policy to apply to a v2 repository's runs.
+    /// * `password` - Password to derive the decryption key from, if the
+    ///   repository is encrypted. Ignored for unencrypted repositories.
+    #[instrument(ret, err, skip(password))]
+    async fn validate_format(
+        root: &NamedDir,
+        retention: RetentionPolicy,
+        password: Option<&str>,
+    ) -> Result<FileRepositoryVersion> {
         let mut format = String::new();
         let opts = *OpenOptions::default().read(true);
         opts.open_at(root, FORMAT_FILE)
@@ -220,11 +691,27 @@ impl File {
         if format != "1\n" && format != "2\n" {
             Err(eyre!("Unknown repository format: {}", format))?
         }
+        let encryptor = match opts.open_at(root, ENCRYPTION_FILE).await {
+            Ok(mut f) => {
+                let mut content = String::new();
+                f.read_to_string(&mut content).await.eyre()?;
+                let header: EncryptionHeader = serde_json::from_str(&content).eyre()?;
+                let password = password.ok_or_else(|| {
+                    eyre!("Repository is encrypted; a password is required to open it")
+                })?;
+                header.encryptor(password)?
+            }
+            Err(_) => Encryptor::None,
+        };
         opts.open_at(root, NEXT_STREAM_FILE).await.map(|_d| {
             if format == "1\n" {
                 FileRepositoryVersion::V1(TestRepositoryV1Repo { root: root.clone() })
             } else {
-                FileRepositoryVersion::V2(TestRepositoryV2Repo { root: root.clone() })
+                FileRepositoryVersion::V2(TestRepositoryV2Repo {
+                    root: root.clone(),
+                    retention,
+                    encryptor,
+                })
             }
         })
     }
@@ -235,6 +722,34 @@ impl Repository for File {
     async fn count(&self) -> Result<usize> {
         self.engine.count().await
     }
+
+    async fn latest_id(&self) -> Result<Option<usize>> {
+        self.engine.latest_id().await
+    }
+
+    async fn open_run(&self) -> Result<RunWriter> {
+        self.engine.open_run().await
+    }
+
+    async fn get_run(&self, id: usize) -> Result<Vec<TestResult>> {
+        self.engine.get_run(id).await
+    }
+
+    async fn prune(&self) -> Result<usize> {
+        self.engine.prune().await
+    }
+
+    async fn failing(&self) -> Result<Vec<TestResult>> {
+        self.engine.failing().await
+    }
+
+    async fn test_times(
+        &self,
+        ids: &[String],
+        default: Duration,
+    ) -> Result<HashMap<String, Duration>> {
+        self.engine.test_times(ids, default).await
+    }
 }
 
 #[cfg(test)]
@@ -13,22 +13,42 @@
 // license you chose for the specific language governing permissions and
 // limitations under that license.
 
-use std::path::Path;
+use std::{path::Path, time::Duration};
 
 use async_trait::async_trait;
 use url::Url;
 
 use crate::{
+    encryption::Cipher,
     error::{Eyrify, Result},
     file::File,
     memory::{Memory, MemoryStore},
     repository,
+    result::TestResult,
+    retention::RetentionPolicy,
+    run::RunWriter,
+    sqlite::Sqlite,
 };
 
 /// Open a repository with options
-#[derive(Debug, Default)]
+#[derive(Default)]
 pub struct OpenOptions<'a> {
     memory_store: Option<&'a MemoryStore>,
+    retention: RetentionPolicy,
+    encryption: Option<(String, Cipher)>,
+}
+
+impl<'a> std::fmt::Debug for OpenOptions<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OpenOptions")
+            .field("memory_store", &self.memory_store)
+            .field("retention", &self.retention)
+            .field(
+                "encryption",
+                &self.encryption.as_ref().map(|(_, cipher)| cipher),
+            )
+            .finish()
+    }
 }
 
 impl<'a> OpenOptions<'a> {
@@ -37,6 +57,26 @@ impl<'a> OpenOptions<'a> {
         self.memory_store = Some(memory_store);
         self
     }
+
+    /// Keep no more than `max_runs` runs, pruning the oldest as new runs are
+    /// committed.
+    pub fn with_max_runs(mut self, max_runs: usize) -> Self {
+        self.retention = self.retention.with_max_runs(max_runs);
+        self
+    }
+
+    /// Keep no run older than `max_age`.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.retention = self.retention.with_max_age(max_age);
+        self
+    }
+
+    /// Supply the password (and cipher) an encrypted file repository was
+    /// created with, so it can be opened.
+    pub fn with_encryption(mut self, password: impl Into<String>, cipher: Cipher) -> Self {
+        self.encryption = Some((password.into(), cipher));
+        self
+    }
 }
 
 /// All the known Repository implementations.
@@ -47,6 +87,8 @@ pub enum Repository {
     Memory(Memory),
     /// Python testrepository compatible file repository
     File(File),
+    /// SQLite backed repository
+    Sqlite(Sqlite),
 }
 
 impl Repository {
@@ -61,14 +103,26 @@ impl Repository {
             "file" => {
                 let path = Path::new(&location.path()[1..]);
                 let path = path.canonicalize().eyre()?;
-                Ok(Self::File(File::new(&path).await?))
+                let file = match &options.encryption {
+                    Some((password, _cipher)) => File::new_with_password(&path, password).await?,
+                    None => File::new(&path).await?,
+                };
+                Ok(Self::File(file.with_retention(options.retention)))
             }
             "memory" => {
                 let relpath = location.host_str().unwrap_or_default();
                 let memory_store = options.memory_store.ok_or_else(|| {
                     eyre::eyre!("Memory store required to open a MemoryRepository")
                 })?;
-                Ok(Self::Memory(Memory::new(relpath, memory_store)?))
+                Ok(Self::Memory(
+                    Memory::new(relpath, memory_store)?.with_retention(options.retention),
+                ))
+            }
+            "sqlite" => {
+                let path = Path::new(&location.path()[1..]);
+                Ok(Self::Sqlite(
+                    Sqlite::new(path).await?.with_retention(options.retention),
+                ))
             }
             _ => Err(eyre::eyre!("Unknown scheme {}", location))?,
         }
@@ -81,6 +135,59 @@ impl repository::Repository for Repository {
         match self {
             Self::Memory(r) => r.count().await,
             Self::File(r) => r.count().await,
+            Self::Sqlite(r) => r.count().await,
+        }
+    }
+
+    async fn latest_id(&self) -> Result<Option<usize>> {
+        match self {
+            Self::Memory(r) => r.latest_id().await,
+            Self::File(r) => r.latest_id().await,
+            Self::Sqlite(r) => r.latest_id().await,
+        }
+    }
+
+    async fn open_run(&self) -> Result<RunWriter> {
+        match self {
+            Self::Memory(r) => r.open_run().await,
+            Self::File(r) => r.open_run().await,
+            Self::Sqlite(r) => r.open_run().await,
+        }
+    }
+
+    async fn get_run(&self, id: usize) -> Result<Vec<TestResult>> {
+        match self {
+            Self::Memory(r) => r.get_run(id).await,
+            Self::File(r) => r.get_run(id).await,
+            Self::Sqlite(r) => r.get_run(id).await,
+        }
+    }
+
+    async fn prune(&self) -> Result<usize> {
+        match self {
+            Self::Memory(r) => r.prune().await,
+            Self::File(r) => r.prune().await,
+            Self::Sqlite(r) => r.prune().await,
+        }
+    }
+
+    async fn failing(&self) -> Result<Vec<TestResult>> {
+        match self {
+            Self::Memory(r) => r.failing().await,
+            Self::File(r) => r.failing().await,
+            Self::Sqlite(r) => r.failing().await,
+        }
+    }
+
+    async fn test_times(
+        &self,
+        ids: &[String],
+        default: Duration,
+    ) -> Result<std::collections::HashMap<String, Duration>> {
+        match self {
+            Self::Memory(r) => r.test_times(ids, default).await,
+            Self::File(r) => r.test_times(ids, default).await,
+            Self::Sqlite(r) => r.test_times(ids, default).await,
         }
     }
 }
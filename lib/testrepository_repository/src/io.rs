@@ -19,22 +19,29 @@
 use std::os::unix::io::AsRawFd;
 #[cfg(windows)]
 use std::os::windows::io::AsRawHandle;
+#[cfg(windows)]
+use std::os::windows::io::{FromRawHandle, IntoRawHandle};
 use std::{
+    collections::VecDeque,
+    ffi::{OsStr, OsString},
     fs::File,
+    future::Future,
     io,
-    os::windows::io::{FromRawHandle, IntoRawHandle},
     path::{Path, PathBuf},
+    pin::Pin,
     sync::Arc,
+    task::{Context, Poll},
 };
 
 use async_trait::async_trait;
+use futures::Stream;
 use tokio::{
     fs::File as TokioFile,
     task::{self},
 };
 use tracing::instrument;
 
-use crate::error::{Eyrify, Result};
+use crate::error::{Eyrify, IoOp, IoOpError, Result};
 
 #[cfg(windows)]
 #[instrument(err)]
@@ -66,16 +73,21 @@ fn _open_dir(p: &Path) -> io::Result<File> {
 
 /// Open a directory from a path. After this, use the fs_at OpenOptions to
 /// manipulate files and directories.
-pub async fn open_dir(p: &Path) -> Result<ArcFile> {
-    task::spawn_blocking({
-        let p = p.to_owned();
+pub async fn open_dir(p: &Path) -> Result<NamedDir> {
+    let path = p.to_owned();
+    let result = task::spawn_blocking({
+        let p = path.clone();
         move || _open_dir(&p)
     })
     .await
-    .eyre()?
-    .eyre()
-    .map(TokioFile::from)
-    .map(Arc::new)
+    .eyre()?;
+    let file = result.map_err(|source| IoOpError {
+        op: IoOp::OpenDir,
+        path: path.clone(),
+        relative_to: None,
+        source,
+    })?;
+    Ok(NamedDir::new(Arc::new(TokioFile::from(file)), path))
 }
 
 /// Workaround for https://github.com/rbtcollins/fs_at/issues/151 -
@@ -87,6 +99,20 @@ pub struct OpenOptions {
     create: bool,
     create_new: bool,
     write_mode: fs_at::OpenOptionsWriteMode,
+    #[cfg(unix)]
+    mode: Option<u32>,
+    #[cfg(unix)]
+    custom_flags: i32,
+    #[cfg(windows)]
+    access_mode: Option<u32>,
+    #[cfg(windows)]
+    share_mode: Option<u32>,
+    #[cfg(windows)]
+    custom_flags: u32,
+    #[cfg(windows)]
+    attributes: u32,
+    #[cfg(windows)]
+    security_qos_flags: u32,
 }
 
 impl OpenOptions {
@@ -110,21 +136,117 @@ impl OpenOptions {
         self
     }
 
-    pub async fn open_blocking_dir<F>(&self, fd: &ArcFile, name: &Path, f: F) -> Result<ArcFile>
+    /// Unset by default, in which case newly created files/dirs get the
+    /// usual `0o666`/`0o777` masked by the process umask, matching
+    /// [`tokio::fs::DirBuilderExt::mode`].
+    #[cfg(unix)]
+    pub fn mode(&mut self, mode: u32) -> &mut Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    #[cfg(unix)]
+    pub fn custom_flags(&mut self, flags: i32) -> &mut Self {
+        self.custom_flags = flags;
+        self
+    }
+
+    /// Mirrors [`std::os::windows::fs::OpenOptionsExt::access_mode`].
+    #[cfg(windows)]
+    pub fn access_mode(&mut self, access_mode: u32) -> &mut Self {
+        self.access_mode = Some(access_mode);
+        self
+    }
+
+    /// Mirrors [`std::os::windows::fs::OpenOptionsExt::share_mode`].
+    #[cfg(windows)]
+    pub fn share_mode(&mut self, share_mode: u32) -> &mut Self {
+        self.share_mode = Some(share_mode);
+        self
+    }
+
+    /// Mirrors [`std::os::windows::fs::OpenOptionsExt::custom_flags`].
+    #[cfg(windows)]
+    pub fn custom_flags(&mut self, custom_flags: u32) -> &mut Self {
+        self.custom_flags = custom_flags;
+        self
+    }
+
+    /// Mirrors [`std::os::windows::fs::OpenOptionsExt::attributes`].
+    #[cfg(windows)]
+    pub fn attributes(&mut self, attributes: u32) -> &mut Self {
+        self.attributes = attributes;
+        self
+    }
+
+    /// Mirrors [`std::os::windows::fs::OpenOptionsExt::security_qos_flags`].
+    #[cfg(windows)]
+    pub fn security_qos_flags(&mut self, security_qos_flags: u32) -> &mut Self {
+        self.security_qos_flags = security_qos_flags;
+        self
+    }
+
+    /// The raw `open(2)`-style flags and mode this maps to, for the
+    /// io_uring fast path in [`crate::io_uring`], which submits `openat2`
+    /// SQEs directly rather than going through `fs_at::OpenOptions`. The mode
+    /// is left as `None` when unset rather than defaulted here, since the
+    /// right default (`0o666` for a file, `0o777` for a directory) depends
+    /// on which operation the caller is building flags for.
+    #[cfg(all(target_os = "linux", feature = "io-uring"))]
+    fn raw_open_flags(&self) -> (i32, Option<u32>) {
+        let mut flags = if matches!(self.write_mode, fs_at::OpenOptionsWriteMode::Write) {
+            if self.read {
+                libc::O_RDWR
+            } else {
+                libc::O_WRONLY
+            }
+        } else {
+            libc::O_RDONLY
+        };
+        if self.create {
+            flags |= libc::O_CREAT;
+        }
+        if self.create_new {
+            flags |= libc::O_CREAT | libc::O_EXCL;
+        }
+        flags |= self.custom_flags;
+        (flags, self.mode)
+    }
+
+    pub async fn open_blocking_dir<F>(
+        &self,
+        op: IoOp,
+        fd: &NamedDir,
+        name: &Path,
+        f: F,
+    ) -> Result<NamedDir>
     where
         F: FnOnce(&fs_at::OpenOptions, &File, PathBuf) -> std::io::Result<File> + Send + 'static,
     {
-        self.open_blocking_file(fd, name, f).await.map(Arc::new)
+        let child_path = fd.path.join(name);
+        self.open_blocking_file(op, fd, name, f)
+            .await
+            .map(|file| NamedDir::new(Arc::new(file), child_path))
     }
 
-    pub async fn open_blocking_file<F>(&self, fd: &ArcFile, name: &Path, f: F) -> Result<TokioFile>
+    pub async fn open_blocking_file<F>(
+        &self,
+        op: IoOp,
+        fd: &NamedDir,
+        name: &Path,
+        f: F,
+    ) -> Result<TokioFile>
     where
         F: FnOnce(&fs_at::OpenOptions, &File, PathBuf) -> std::io::Result<File> + Send + 'static,
     {
-        task::spawn_blocking({
+        // Captured before `name` is moved into the blocking closure below, so
+        // it is still around to annotate the error afterwards.
+        let path = name.to_owned();
+        let relative_to = format!("{:?}", fd.path);
+        let result = task::spawn_blocking({
             let owned_self = *self;
-            let name = PathBuf::from(name);
-            let owned_fd = Arc::clone(fd);
+            let name = path.clone();
+            let owned_fd = Arc::clone(&fd.file);
             move || {
                 // Safety: owned_fd, the tokio File, is moved into the closure and dropped after the fs_at all
                 // completes, so it lives long enough. As it is within Arc, no mut ref can exist until the drop
@@ -146,8 +268,57 @@ impl OpenOptions {
             }
         })
         .await
-        .eyre()?
-        .eyre()
+        .eyre()?;
+        result.map_err(|source| {
+            IoOpError {
+                op,
+                path,
+                relative_to: Some(relative_to),
+                source,
+            }
+            .into()
+        })
+    }
+
+    /// As [`Self::open_blocking_file`], but for operations that act on a
+    /// single name relative to a directory handle without needing
+    /// `fs_at::OpenOptions`, such as `unlink_at`/`rmdir_at`/`symlink_at`/
+    /// `readlink_at`.
+    async fn blocking_value<T, F>(&self, op: IoOp, fd: &NamedDir, name: &Path, f: F) -> Result<T>
+    where
+        F: FnOnce(&File, &Path) -> std::io::Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let path = name.to_owned();
+        let relative_to = format!("{:?}", fd.path);
+        let result = task::spawn_blocking({
+            let name = path.clone();
+            let owned_fd = Arc::clone(&fd.file);
+            move || {
+                #[cfg(windows)]
+                let std_fd = unsafe { File::from_raw_handle(owned_fd.as_raw_handle()) };
+                #[cfg(not(windows))]
+                let std_fd = unsafe { File::from_raw_fd(owned_fd.as_raw_fd()) };
+                let r = f(&std_fd, &name);
+                #[cfg(windows)]
+                std_fd.into_raw_handle();
+                #[cfg(not(windows))]
+                std_fd.into_raw_fd();
+                drop(owned_fd);
+                r
+            }
+        })
+        .await
+        .eyre()?;
+        result.map_err(|source| {
+            IoOpError {
+                op,
+                path,
+                relative_to: Some(relative_to),
+                source,
+            }
+            .into()
+        })
     }
 }
 
@@ -158,6 +329,25 @@ impl From<OpenOptions> for fs_at::OpenOptions {
             .create(s.create)
             .create_new(s.create_new)
             .write(s.write_mode);
+        #[cfg(unix)]
+        {
+            if let Some(mode) = s.mode {
+                opts.mode(mode);
+            }
+            opts.custom_flags(s.custom_flags);
+        }
+        #[cfg(windows)]
+        {
+            if let Some(access_mode) = s.access_mode {
+                opts.access_mode(access_mode);
+            }
+            if let Some(share_mode) = s.share_mode {
+                opts.share_mode(share_mode);
+            }
+            opts.custom_flags(s.custom_flags)
+                .attributes(s.attributes)
+                .security_qos_flags(s.security_qos_flags);
+        }
         opts
     }
 }
@@ -168,30 +358,671 @@ impl From<OpenOptions> for fs_at::OpenOptions {
 /// until the blocking call completes.
 pub type ArcFile = Arc<TokioFile>;
 
+/// An open directory handle paired with the path it was opened at (or, for a
+/// directory reached via a chain of `*_at` calls, the path it's known by
+/// relative to that chain's root). `ArcFile` itself carries no path by
+/// design, so a bare `IoOpError` built from one alone could only report a raw
+/// fd/handle number; this is what every `*_at` operation below threads
+/// through instead, so errors read like `mkdir_at("subdir") relative to
+/// "/base": File exists`.
+///
+/// Carried purely for diagnostics: `*_at` operations are still performed
+/// against the fd, never by re-resolving `path`.
+#[derive(Debug, Clone)]
+pub struct NamedDir {
+    pub file: ArcFile,
+    pub path: PathBuf,
+}
+
+impl NamedDir {
+    pub fn new(file: ArcFile, path: PathBuf) -> Self {
+        Self { file, path }
+    }
+}
+
 /// Async version of fs_at::OpenOptions.
 #[async_trait]
 pub trait AsyncOptionOptions {
-    async fn mkdir_at<P: AsRef<Path> + Send>(&self, dir: &ArcFile, name: P) -> Result<ArcFile>;
+    async fn mkdir_at<P: AsRef<Path> + Send>(&self, dir: &NamedDir, name: P) -> Result<NamedDir>;
+
+    async fn open_dir_at<P: AsRef<Path> + Send>(&self, d: &NamedDir, p: P) -> Result<NamedDir>;
+
+    async fn open_at<P: AsRef<Path> + Send>(&self, d: &NamedDir, p: P) -> Result<TokioFile>;
+
+    /// Remove the file `name` within `dir`. Mirrors [`tokio::fs::remove_file`].
+    async fn unlink_at<P: AsRef<Path> + Send>(&self, dir: &NamedDir, name: P) -> Result<()>;
+
+    /// Remove the (empty) directory `name` within `dir`. Mirrors
+    /// [`tokio::fs::remove_dir`].
+    async fn rmdir_at<P: AsRef<Path> + Send>(&self, dir: &NamedDir, name: P) -> Result<()>;
+
+    /// Rename `old_name` within `old_dir` to `new_name` within `new_dir`.
+    /// Mirrors [`tokio::fs::rename`].
+    async fn rename_at<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(
+        &self,
+        old_dir: &NamedDir,
+        old_name: P,
+        new_dir: &NamedDir,
+        new_name: Q,
+    ) -> Result<()>;
 
-    async fn open_dir_at<P: AsRef<Path> + Send>(&self, d: &ArcFile, p: P) -> Result<ArcFile>;
+    /// Create a symlink named `name` within `dir`, pointing at `target`.
+    /// Mirrors [`tokio::fs::symlink`].
+    async fn symlink_at<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(
+        &self,
+        target: P,
+        dir: &NamedDir,
+        name: Q,
+    ) -> Result<()>;
 
-    async fn open_at<P: AsRef<Path> + Send>(&self, d: &ArcFile, p: P) -> Result<TokioFile>;
+    /// Enumerate the entries of `dir`, without ever resolving it to a path.
+    /// Mirrors [`tokio::fs::read_dir`], but the returned [`ReadDirAt`] reads
+    /// entries in batches to amortize the blocking-pool round trip rather
+    /// than one `spawn_blocking` per entry.
+    async fn read_dir_at(&self, dir: &NamedDir) -> Result<ReadDirAt>;
+
+    /// The target of the symlink `name` within `dir`. Mirrors
+    /// [`tokio::fs::read_link`].
+    async fn readlink_at<P: AsRef<Path> + Send>(&self, dir: &NamedDir, name: P)
+        -> Result<PathBuf>;
+
+    /// Resolve `path`'s components relative to `dir`, following any
+    /// symlinks encountered along the way, up to [`MAX_SYMLINK_DEPTH`]
+    /// levels deep before giving up with an error (matching `ELOOP`).
+    /// Mirrors [`tokio::fs::canonicalize`], but anchored to a directory
+    /// handle instead of resolved from an absolute path.
+    async fn canonicalize_at<P: AsRef<Path> + Send>(
+        &self,
+        dir: &NamedDir,
+        path: P,
+    ) -> Result<PathBuf>;
 }
 
 #[async_trait]
 impl AsyncOptionOptions for OpenOptions {
-    async fn mkdir_at<P: AsRef<Path> + Send>(&self, dir: &ArcFile, name: P) -> Result<ArcFile> {
-        self.open_blocking_dir(dir, name.as_ref(), move |s, d, name| s.mkdir_at(d, name))
-            .await
+    async fn mkdir_at<P: AsRef<Path> + Send>(&self, dir: &NamedDir, name: P) -> Result<NamedDir> {
+        #[cfg(all(target_os = "linux", feature = "io-uring"))]
+        if let Some(ring) = crate::io_uring::ring() {
+            let (_, mode) = self.raw_open_flags();
+            // Directories need their own default: falling back to the file
+            // default of `0o666` would create a directory with no
+            // execute/search bit, making it impossible to enter or list.
+            ring.mkdir_at(&dir.file, name.as_ref(), mode.unwrap_or(0o777))
+                .await
+                .map_err(|source| IoOpError {
+                    op: IoOp::MkdirAt,
+                    path: name.as_ref().to_owned(),
+                    relative_to: Some(format!("{:?}", dir.path)),
+                    source,
+                })?;
+            return self.open_dir_at(dir, name).await;
+        }
+        self.open_blocking_dir(IoOp::MkdirAt, dir, name.as_ref(), move |s, d, name| {
+            s.mkdir_at(d, name)
+        })
+        .await
     }
 
-    async fn open_dir_at<P: AsRef<Path> + Send>(&self, d: &ArcFile, p: P) -> Result<ArcFile> {
-        self.open_blocking_dir(d, p.as_ref(), move |s, d, name| s.open_dir_at(d, name))
-            .await
+    async fn open_dir_at<P: AsRef<Path> + Send>(&self, d: &NamedDir, p: P) -> Result<NamedDir> {
+        #[cfg(all(target_os = "linux", feature = "io-uring"))]
+        if let Some(ring) = crate::io_uring::ring() {
+            let (flags, mode) = self.raw_open_flags();
+            let file = ring
+                .open_at(
+                    &d.file,
+                    p.as_ref(),
+                    flags | libc::O_DIRECTORY,
+                    mode.unwrap_or(0o666),
+                )
+                .await
+                .map_err(|source| IoOpError {
+                    op: IoOp::OpenDirAt,
+                    path: p.as_ref().to_owned(),
+                    relative_to: Some(format!("{:?}", d.path)),
+                    source,
+                })?;
+            return Ok(NamedDir::new(
+                Arc::new(TokioFile::from(file)),
+                d.path.join(p.as_ref()),
+            ));
+        }
+        self.open_blocking_dir(IoOp::OpenDirAt, d, p.as_ref(), move |s, d, name| {
+            s.open_dir_at(d, name)
+        })
+        .await
     }
 
-    async fn open_at<P: AsRef<Path> + Send>(&self, d: &ArcFile, p: P) -> Result<TokioFile> {
-        self.open_blocking_file(d, p.as_ref(), move |s, d, name| s.open_at(d, name))
-            .await
+    async fn open_at<P: AsRef<Path> + Send>(&self, d: &NamedDir, p: P) -> Result<TokioFile> {
+        #[cfg(all(target_os = "linux", feature = "io-uring"))]
+        if let Some(ring) = crate::io_uring::ring() {
+            let (flags, mode) = self.raw_open_flags();
+            let file = ring
+                .open_at(&d.file, p.as_ref(), flags, mode.unwrap_or(0o666))
+                .await
+                .map_err(|source| IoOpError {
+                    op: IoOp::OpenAt,
+                    path: p.as_ref().to_owned(),
+                    relative_to: Some(format!("{:?}", d.path)),
+                    source,
+                })?;
+            return Ok(TokioFile::from(file));
+        }
+        self.open_blocking_file(IoOp::OpenAt, d, p.as_ref(), move |s, d, name| {
+            s.open_at(d, name)
+        })
+        .await
+    }
+
+    async fn unlink_at<P: AsRef<Path> + Send>(&self, dir: &NamedDir, name: P) -> Result<()> {
+        #[cfg(all(target_os = "linux", feature = "io-uring"))]
+        if let Some(ring) = crate::io_uring::ring() {
+            return ring
+                .unlink_at(&dir.file, name.as_ref(), false)
+                .await
+                .map_err(|source| {
+                    IoOpError {
+                        op: IoOp::UnlinkAt,
+                        path: name.as_ref().to_owned(),
+                        relative_to: Some(format!("{:?}", dir.path)),
+                        source,
+                    }
+                    .into()
+                });
+        }
+        self.blocking_value(IoOp::UnlinkAt, dir, name.as_ref(), move |d, name| {
+            fs_at::unlink_at(d, name, fs_at::UnlinkatFlags::empty())
+        })
+        .await
+    }
+
+    async fn rmdir_at<P: AsRef<Path> + Send>(&self, dir: &NamedDir, name: P) -> Result<()> {
+        #[cfg(all(target_os = "linux", feature = "io-uring"))]
+        if let Some(ring) = crate::io_uring::ring() {
+            return ring
+                .unlink_at(&dir.file, name.as_ref(), true)
+                .await
+                .map_err(|source| {
+                    IoOpError {
+                        op: IoOp::RmdirAt,
+                        path: name.as_ref().to_owned(),
+                        relative_to: Some(format!("{:?}", dir.path)),
+                        source,
+                    }
+                    .into()
+                });
+        }
+        self.blocking_value(IoOp::RmdirAt, dir, name.as_ref(), move |d, name| {
+            fs_at::unlink_at(d, name, fs_at::UnlinkatFlags::REMOVE_DIR)
+        })
+        .await
+    }
+
+    async fn rename_at<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(
+        &self,
+        old_dir: &NamedDir,
+        old_name: P,
+        new_dir: &NamedDir,
+        new_name: Q,
+    ) -> Result<()> {
+        let old_path = old_name.as_ref().to_owned();
+        let new_path = new_name.as_ref().to_owned();
+        #[cfg(all(target_os = "linux", feature = "io-uring"))]
+        if let Some(ring) = crate::io_uring::ring() {
+            return ring
+                .rename_at(&old_dir.file, &old_path, &new_dir.file, &new_path)
+                .await
+                .map_err(|source| {
+                    IoOpError {
+                        op: IoOp::RenameAt,
+                        path: old_path,
+                        relative_to: Some(format!("{:?}", old_dir.path)),
+                        source,
+                    }
+                    .into()
+                });
+        }
+        let relative_to = format!("{:?}", old_dir.path);
+        let result = task::spawn_blocking({
+            let old_name = old_path.clone();
+            let new_name = new_path.clone();
+            let owned_old_dir = Arc::clone(&old_dir.file);
+            let owned_new_dir = Arc::clone(&new_dir.file);
+            move || {
+                #[cfg(windows)]
+                let std_old_dir = unsafe { File::from_raw_handle(owned_old_dir.as_raw_handle()) };
+                #[cfg(not(windows))]
+                let std_old_dir = unsafe { File::from_raw_fd(owned_old_dir.as_raw_fd()) };
+                #[cfg(windows)]
+                let std_new_dir = unsafe { File::from_raw_handle(owned_new_dir.as_raw_handle()) };
+                #[cfg(not(windows))]
+                let std_new_dir = unsafe { File::from_raw_fd(owned_new_dir.as_raw_fd()) };
+                let r = fs_at::rename_at(
+                    &std_old_dir,
+                    &old_name,
+                    &std_new_dir,
+                    &new_name,
+                    fs_at::RenameatFlags::empty(),
+                );
+                #[cfg(windows)]
+                {
+                    std_old_dir.into_raw_handle();
+                    std_new_dir.into_raw_handle();
+                }
+                #[cfg(not(windows))]
+                {
+                    std_old_dir.into_raw_fd();
+                    std_new_dir.into_raw_fd();
+                }
+                drop(owned_old_dir);
+                drop(owned_new_dir);
+                r
+            }
+        })
+        .await
+        .eyre()?;
+        result.map_err(|source| {
+            IoOpError {
+                op: IoOp::RenameAt,
+                path: old_path,
+                relative_to: Some(relative_to),
+                source,
+            }
+            .into()
+        })
+    }
+
+    async fn symlink_at<P: AsRef<Path> + Send, Q: AsRef<Path> + Send>(
+        &self,
+        target: P,
+        dir: &NamedDir,
+        name: Q,
+    ) -> Result<()> {
+        let target = target.as_ref().to_owned();
+        self.blocking_value(IoOp::SymlinkAt, dir, name.as_ref(), move |d, name| {
+            fs_at::symlink_at(&target, d, name)
+        })
+        .await
+    }
+
+    async fn read_dir_at(&self, dir: &NamedDir) -> Result<ReadDirAt> {
+        let relative_to = format!("{:?}", dir.path);
+        let owned_fd = Arc::clone(&dir.file);
+        let result = task::spawn_blocking(move || {
+            #[cfg(windows)]
+            let std_fd = unsafe { File::from_raw_handle(owned_fd.as_raw_handle()) };
+            #[cfg(not(windows))]
+            let std_fd = unsafe { File::from_raw_fd(owned_fd.as_raw_fd()) };
+            let r = fs_at::read_dir_at(&std_fd);
+            #[cfg(windows)]
+            std_fd.into_raw_handle();
+            #[cfg(not(windows))]
+            std_fd.into_raw_fd();
+            drop(owned_fd);
+            r
+        })
+        .await
+        .eyre()?;
+        let inner = result.map_err(|source| {
+            IoOpError {
+                op: IoOp::ReadDirAt,
+                path: PathBuf::new(),
+                relative_to: Some(relative_to.clone()),
+                source,
+            }
+            .into()
+        })?;
+        Ok(ReadDirAt::new(inner, relative_to))
+    }
+
+    async fn readlink_at<P: AsRef<Path> + Send>(
+        &self,
+        dir: &NamedDir,
+        name: P,
+    ) -> Result<PathBuf> {
+        self.blocking_value(IoOp::ReadlinkAt, dir, name.as_ref(), move |d, name| {
+            fs_at::readlink_at(d, name)
+        })
+        .await
+    }
+
+    async fn canonicalize_at<P: AsRef<Path> + Send>(
+        &self,
+        dir: &NamedDir,
+        path: P,
+    ) -> Result<PathBuf> {
+        let mut pending: VecDeque<OsString> =
+            path.as_ref().iter().map(OsStr::to_owned).collect();
+        let mut current = dir.clone();
+        let mut resolved = PathBuf::new();
+        let mut links_followed = 0usize;
+
+        while let Some(component) = pending.pop_front() {
+            // Every component (not just the last) is tried as a symlink
+            // first: `open_dir_at` below would happily follow one at the
+            // kernel level to reach the right directory, but the *name* it
+            // was opened through would still be the symlink's, not its
+            // target's, so resolving only the final component would leave
+            // earlier ones unresolved in the returned path.
+            match self.readlink_at(&current, &component).await {
+                Ok(target) => {
+                    links_followed += 1;
+                    if links_followed > MAX_SYMLINK_DEPTH {
+                        return Err(eyre::eyre!(
+                            "too many levels of symbolic links resolving {:?} (ELOOP)",
+                            path.as_ref(),
+                        )
+                        .into());
+                    }
+                    if target.is_absolute() {
+                        return Err(eyre::eyre!(
+                            "cannot resolve absolute symlink target {:?} relative to a directory handle",
+                            target
+                        )
+                        .into());
+                    }
+                    let mut replacement: VecDeque<OsString> =
+                        target.iter().map(OsStr::to_owned).collect();
+                    replacement.extend(pending.drain(..));
+                    pending = replacement;
+                }
+                // `readlink_at` on a non-symlink fails with `EINVAL`; treat
+                // only that as "not a symlink" and resolve it literally.
+                // Anything else (doesn't exist, permission denied, a stale
+                // fd, ...) must propagate, since a canonicalize that can't
+                // see the whole path shouldn't silently report success.
+                Err(crate::error::Error::Io(IoOpError { source, .. }))
+                    if source.kind() == io::ErrorKind::InvalidInput =>
+                {
+                    resolved.push(&component);
+                    if !pending.is_empty() {
+                        current = self.open_dir_at(&current, &component).await?;
+                    }
+                }
+                Err(other) => return Err(other),
+            }
+        }
+        Ok(resolved)
+    }
+}
+
+/// Bound on symlink chases in [`AsyncOptionOptions::canonicalize_at`],
+/// matching the `ELOOP` depth most kernels enforce.
+const MAX_SYMLINK_DEPTH: usize = 40;
+
+/// One entry from a directory enumerated via
+/// [`AsyncOptionOptions::read_dir_at`].
+#[derive(Debug, Clone)]
+pub struct DirEntryAt(fs_at::DirEntry);
+
+impl DirEntryAt {
+    pub fn file_name(&self) -> OsString {
+        self.0.file_name()
+    }
+
+    /// Cheap: backed by the raw dirent's `d_type` where the OS reports one,
+    /// falling back to an `fstatat`-style call only when it doesn't.
+    pub fn file_type(&self) -> Result<fs_at::FileType> {
+        self.0.file_type().eyre()
+    }
+}
+
+const READ_DIR_BATCH: usize = 32;
+
+/// A batch of entries read in one blocking-pool round trip, and the raw
+/// iterator to resume from on the next refill (`None` once exhausted).
+type ReadDirBatch = (Option<fs_at::ReadDirAt>, Vec<io::Result<fs_at::DirEntry>>);
+
+fn read_dir_batch(mut inner: fs_at::ReadDirAt) -> ReadDirBatch {
+    let mut batch = Vec::with_capacity(READ_DIR_BATCH);
+    for _ in 0..READ_DIR_BATCH {
+        match inner.next() {
+            Some(entry) => batch.push(entry),
+            None => return (None, batch),
+        }
+    }
+    (Some(inner), batch)
+}
+
+/// A [`Stream`] of [`DirEntryAt`], relative to an already-open directory
+/// handle. Entries are read [`READ_DIR_BATCH`] at a time so that draining the
+/// stream costs one blocking-pool round trip per batch rather than one per
+/// entry; the next batch is read in the background as soon as the previous
+/// one arrives, and is awaited once the buffer runs dry.
+pub struct ReadDirAt {
+    relative_to: String,
+    buffer: VecDeque<io::Result<fs_at::DirEntry>>,
+    refill: Option<task::JoinHandle<ReadDirBatch>>,
+}
+
+impl ReadDirAt {
+    fn new(inner: fs_at::ReadDirAt, relative_to: String) -> Self {
+        ReadDirAt {
+            relative_to,
+            buffer: VecDeque::new(),
+            refill: Some(task::spawn_blocking(move || read_dir_batch(inner))),
+        }
+    }
+}
+
+impl Stream for ReadDirAt {
+    type Item = Result<DirEntryAt>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(entry) = this.buffer.pop_front() {
+                let relative_to = this.relative_to.clone();
+                return Poll::Ready(Some(entry.map(DirEntryAt).map_err(|source| {
+                    IoOpError {
+                        op: IoOp::ReadDirAt,
+                        path: PathBuf::new(),
+                        relative_to: Some(relative_to),
+                        source,
+                    }
+                    .into()
+                })));
+            }
+            let refill = match this.refill.as_mut() {
+                Some(refill) => refill,
+                None => return Poll::Ready(None),
+            };
+            match Pin::new(refill).poll(cx) {
+                Poll::Ready(Ok((inner, batch))) => {
+                    this.buffer.extend(batch);
+                    this.refill = inner.map(|inner| task::spawn_blocking(move || read_dir_batch(inner)));
+                }
+                Poll::Ready(Err(source)) => {
+                    this.refill = None;
+                    return Poll::Ready(Some(Err(source).eyre()));
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_test::{assert_err, assert_ok};
+    use tracing_test::traced_test;
+
+    use super::*;
+
+    async fn root(dir: &tempfile::TempDir) -> NamedDir {
+        assert_ok!(open_dir(dir.path()).await)
+    }
+
+    fn write_opts() -> OpenOptions {
+        *OpenOptions::default()
+            .create(true)
+            .write(fs_at::OpenOptionsWriteMode::Write)
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn mkdir_at_default_mode_is_enterable() {
+        // Regression test for the chunk1-5 bug where a directory created via
+        // the io_uring fast path with no explicit `.mode()` got the file
+        // default `0o666`, leaving it with no execute/search bit.
+        let dir = tempfile::tempdir().unwrap();
+        let root = root(&dir).await;
+        let child = assert_ok!(OpenOptions::default().mkdir_at(&root, "subdir").await);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let meta = std::fs::metadata(dir.path().join("subdir")).unwrap();
+            assert_eq!(meta.permissions().mode() & 0o111, 0o111);
+        }
+        assert_ok!(
+            OpenOptions::default()
+                .read(true)
+                .open_dir_at(&child, ".")
+                .await
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn open_at_write_read_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = root(&dir).await;
+        let mut f = assert_ok!(write_opts().open_at(&root, "hello").await);
+        f.write_all(b"hi").await.unwrap();
+        drop(f);
+        let mut f = assert_ok!(OpenOptions::default().read(true).open_at(&root, "hello").await);
+        let mut content = String::new();
+        f.read_to_string(&mut content).await.unwrap();
+        assert_eq!(content, "hi");
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn unlink_at_removes_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = root(&dir).await;
+        assert_ok!(write_opts().open_at(&root, "bye").await);
+        assert_ok!(OpenOptions::default().unlink_at(&root, "bye").await);
+        assert_err!(OpenOptions::default().read(true).open_at(&root, "bye").await);
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn rmdir_at_removes_empty_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = root(&dir).await;
+        assert_ok!(OpenOptions::default().mkdir_at(&root, "empty").await);
+        assert_ok!(OpenOptions::default().rmdir_at(&root, "empty").await);
+        assert_err!(
+            OpenOptions::default()
+                .read(true)
+                .open_dir_at(&root, "empty")
+                .await
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn rename_at_moves_between_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = root(&dir).await;
+        assert_ok!(write_opts().open_at(&root, "a").await);
+        let other = assert_ok!(OpenOptions::default().mkdir_at(&root, "other").await);
+        assert_ok!(
+            OpenOptions::default()
+                .rename_at(&root, "a", &other, "b")
+                .await
+        );
+        assert_err!(OpenOptions::default().read(true).open_at(&root, "a").await);
+        assert_ok!(
+            OpenOptions::default()
+                .read(true)
+                .open_at(&other, "b")
+                .await
+        );
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn symlink_and_readlink_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = root(&dir).await;
+        assert_ok!(
+            OpenOptions::default()
+                .symlink_at("target", &root, "link")
+                .await
+        );
+        let target = assert_ok!(OpenOptions::default().readlink_at(&root, "link").await);
+        assert_eq!(target, PathBuf::from("target"));
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn read_dir_at_enumerates_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = root(&dir).await;
+        assert_ok!(write_opts().open_at(&root, "one").await);
+        assert_ok!(write_opts().open_at(&root, "two").await);
+        let entries: Vec<_> = assert_ok!(OpenOptions::default().read_dir_at(&root).await)
+            .collect::<Vec<_>>()
+            .await;
+        let mut names: Vec<_> = entries.into_iter().map(|e| assert_ok!(e).file_name()).collect();
+        names.sort();
+        assert_eq!(names, vec![OsString::from("one"), OsString::from("two")]);
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn canonicalize_at_resolves_symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = root(&dir).await;
+        assert_ok!(write_opts().open_at(&root, "real").await);
+        assert_ok!(
+            OpenOptions::default()
+                .symlink_at("real", &root, "link")
+                .await
+        );
+        let resolved = assert_ok!(OpenOptions::default().canonicalize_at(&root, "link").await);
+        assert_eq!(resolved, PathBuf::from("real"));
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn canonicalize_at_resolves_intermediate_symlinks() {
+        // Regression test: a symlink in a non-final position must also be
+        // resolved, not just passed through literally because `open_dir_at`
+        // happened to follow it at the kernel level to reach the real
+        // directory.
+        let dir = tempfile::tempdir().unwrap();
+        let root = root(&dir).await;
+        let realdir = assert_ok!(OpenOptions::default().mkdir_at(&root, "realdir").await);
+        assert_ok!(write_opts().open_at(&realdir, "file").await);
+        assert_ok!(
+            OpenOptions::default()
+                .symlink_at("realdir", &root, "link")
+                .await
+        );
+        let resolved = assert_ok!(
+            OpenOptions::default()
+                .canonicalize_at(&root, "link/file")
+                .await
+        );
+        assert_eq!(resolved, PathBuf::from("realdir/file"));
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn canonicalize_at_propagates_non_symlink_errors() {
+        // Regression test for the chunk1-6 bug where any readlink_at error
+        // on the final component (not just "not a symlink") was swallowed
+        // and the path reported as resolved.
+        let dir = tempfile::tempdir().unwrap();
+        let root = root(&dir).await;
+        assert_err!(
+            OpenOptions::default()
+                .canonicalize_at(&root, "does-not-exist")
+                .await
+        );
     }
 }
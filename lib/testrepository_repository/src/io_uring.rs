@@ -0,0 +1,252 @@
+//! Optional Linux io_uring backend for the `*_at` operations in [`crate::io`].
+//!
+//! A single ring is shared by the whole process. Submission happens from
+//! whichever task calls in; a dedicated reaper thread owns waiting on
+//! completions (`io_uring_enter` blocks, so it cannot run on the async
+//! executor) and resolves each caller's future from the matching CQE.
+//!
+//! Not every kernel this crate runs on supports the opcodes used here
+//! (`openat2`, `mkdirat`, `unlinkat`, `renameat`), so [`ring`] probes once on
+//! first use and returns `None` if the ring can't be created or any of those
+//! opcodes is unsupported; callers in
+//! [`crate::io`] fall back to the portable `spawn_blocking` path whenever
+//! that happens.
+
+use std::{
+    collections::HashMap,
+    ffi::CString,
+    io,
+    os::unix::{ffi::OsStrExt, io::AsRawFd},
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    time::Duration,
+};
+
+use io_uring::{opcode, types, IoUring};
+use tokio::sync::oneshot;
+
+use crate::io::ArcFile;
+
+/// The process-wide ring, or `None` if this kernel can't back it. Probed
+/// once, lazily, since creating a ring is itself a syscall we don't want to
+/// pay on every call.
+pub(crate) fn ring() -> Option<Arc<Ring>> {
+    static RING: OnceLock<Option<Arc<Ring>>> = OnceLock::new();
+    RING.get_or_init(Ring::probe).clone()
+}
+
+/// The name/path buffers an SQE points into. The kernel keeps reading these
+/// until the CQE is reaped, which can outlive the submitting future (it may
+/// be cancelled at any `.await` point), so ownership lives here rather than
+/// on the submitting task's stack. `CString`s are heap-allocated, so their
+/// address is stable across the move into this enum; `OpenHow` is boxed for
+/// the same reason, since `openat2` is given a pointer to it directly.
+enum Buffers {
+    Open {
+        _name: CString,
+        _how: Box<types::OpenHow>,
+    },
+    Name {
+        _name: CString,
+    },
+    Rename {
+        _old_name: CString,
+        _new_name: CString,
+    },
+}
+
+/// One submission kept alive until its CQE arrives: the directory fd(s) and
+/// any buffers the SQE points into must stay valid for the whole in-flight
+/// operation even if the submitting future is cancelled, and the caller
+/// learns the raw result (a non-negative count, or `-errno`) via `done`.
+struct Inflight {
+    _dirs: (ArcFile, Option<ArcFile>),
+    _buffers: Buffers,
+    done: oneshot::Sender<i32>,
+}
+
+/// How long the reaper sleeps between polls when the ring has nothing ready,
+/// so an idle ring doesn't spin.
+const REAP_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+pub(crate) struct Ring {
+    // io_uring's submission and completion queues are not safe to touch
+    // concurrently from multiple threads, so every access goes through this
+    // lock. The reaper must never block while holding it: once the ring is
+    // idle there's nothing left to produce a completion, and a blocking wait
+    // taken here would starve every `submit()` call that needs the same lock
+    // to push its SQE (which in turn is what the reaper is waiting on).
+    uring: Mutex<IoUring>,
+    inflight: Mutex<HashMap<u64, Inflight>>,
+    next_user_data: AtomicU64,
+}
+
+impl Ring {
+    fn probe() -> Option<Arc<Ring>> {
+        let uring = IoUring::new(128).ok()?;
+        let mut probe = io_uring::Probe::new();
+        uring.submitter().register_probe(&mut probe).ok()?;
+        let supported = [
+            opcode::OpenAt2::CODE,
+            opcode::MkDirAt::CODE,
+            opcode::UnlinkAt::CODE,
+            opcode::RenameAt::CODE,
+        ]
+        .into_iter()
+        .all(|code| probe.is_supported(code));
+        if !supported {
+            return None;
+        }
+        let ring = Arc::new(Ring {
+            uring: Mutex::new(uring),
+            inflight: Mutex::new(HashMap::new()),
+            next_user_data: AtomicU64::new(1),
+        });
+        let reaper = Arc::clone(&ring);
+        std::thread::Builder::new()
+            .name("testrepository-io-uring-reaper".into())
+            .spawn(move || reaper.reap_forever())
+            .ok()?;
+        Some(ring)
+    }
+
+    fn reap_forever(&self) {
+        loop {
+            let results: Vec<(u64, i32)> = {
+                let mut uring = self.uring.lock().unwrap();
+                // Non-blocking: flush any SQEs a concurrent `submit()` pushed
+                // and collect whatever CQEs are already ready, then release
+                // the lock immediately rather than waiting on more while
+                // holding it.
+                if uring.submit().is_err() {
+                    return;
+                }
+                uring
+                    .completion()
+                    .map(|cqe| (cqe.user_data(), cqe.result()))
+                    .collect()
+            };
+            if results.is_empty() {
+                std::thread::sleep(REAP_POLL_INTERVAL);
+                continue;
+            }
+            let mut inflight = self.inflight.lock().unwrap();
+            for (user_data, result) in results {
+                if let Some(entry) = inflight.remove(&user_data) {
+                    let _ = entry.done.send(result);
+                }
+            }
+        }
+    }
+
+    async fn submit(
+        &self,
+        dirs: (ArcFile, Option<ArcFile>),
+        buffers: Buffers,
+        entry: io_uring::squeue::Entry,
+    ) -> io::Result<i32> {
+        let user_data = self.next_user_data.fetch_add(1, Ordering::Relaxed);
+        let entry = entry.user_data(user_data);
+        let (done, result) = oneshot::channel();
+        self.inflight.lock().unwrap().insert(
+            user_data,
+            Inflight {
+                _dirs: dirs,
+                _buffers: buffers,
+                done,
+            },
+        );
+        {
+            let mut uring = self.uring.lock().unwrap();
+            // SAFETY: the buffers `entry` points at (the directory fd and
+            // the name/`OpenHow` buffers now owned by the `Inflight` we just
+            // registered above) are kept alive until this op's CQE is
+            // reaped, regardless of whether the submitting future itself is
+            // later cancelled.
+            unsafe {
+                uring
+                    .submission()
+                    .push(&entry)
+                    .map_err(|_| io::Error::other("io_uring submission queue full"))?;
+            }
+            uring.submit()?;
+        }
+        let raw = result
+            .await
+            .map_err(|_| io::Error::other("io_uring reaper thread exited"))?;
+        if raw < 0 {
+            Err(io::Error::from_raw_os_error(-raw))
+        } else {
+            Ok(raw)
+        }
+    }
+
+    pub(crate) async fn open_at(
+        &self,
+        dir: &ArcFile,
+        name: &Path,
+        flags: i32,
+        mode: u32,
+    ) -> io::Result<std::fs::File> {
+        let name = CString::new(name.as_os_str().as_bytes())?;
+        let mut how = Box::new(types::OpenHow::new().flags(flags as u64).mode(mode as u64));
+        let fd = types::Fd(dir.as_raw_fd());
+        let entry = opcode::OpenAt2::new(fd, name.as_ptr(), how.as_mut()).build();
+        let buffers = Buffers::Open {
+            _name: name,
+            _how: how,
+        };
+        let raw_fd = self.submit((dir.clone(), None), buffers, entry).await?;
+        // SAFETY: the kernel just handed us a freshly opened, uniquely owned
+        // fd for this raw_fd.
+        Ok(unsafe { <std::fs::File as std::os::unix::io::FromRawFd>::from_raw_fd(raw_fd) })
+    }
+
+    pub(crate) async fn mkdir_at(&self, dir: &ArcFile, name: &Path, mode: u32) -> io::Result<()> {
+        let name = CString::new(name.as_os_str().as_bytes())?;
+        let fd = types::Fd(dir.as_raw_fd());
+        let entry = opcode::MkDirAt::new(fd, name.as_ptr()).mode(mode).build();
+        let buffers = Buffers::Name { _name: name };
+        self.submit((dir.clone(), None), buffers, entry)
+            .await
+            .map(|_| ())
+    }
+
+    pub(crate) async fn unlink_at(&self, dir: &ArcFile, name: &Path, is_dir: bool) -> io::Result<()> {
+        let name = CString::new(name.as_os_str().as_bytes())?;
+        let fd = types::Fd(dir.as_raw_fd());
+        let mut entry = opcode::UnlinkAt::new(fd, name.as_ptr());
+        if is_dir {
+            entry = entry.flags(libc::AT_REMOVEDIR);
+        }
+        let buffers = Buffers::Name { _name: name };
+        self.submit((dir.clone(), None), buffers, entry.build())
+            .await
+            .map(|_| ())
+    }
+
+    pub(crate) async fn rename_at(
+        &self,
+        old_dir: &ArcFile,
+        old_name: &Path,
+        new_dir: &ArcFile,
+        new_name: &Path,
+    ) -> io::Result<()> {
+        let old_name = CString::new(old_name.as_os_str().as_bytes())?;
+        let new_name = CString::new(new_name.as_os_str().as_bytes())?;
+        let old_fd = types::Fd(old_dir.as_raw_fd());
+        let new_fd = types::Fd(new_dir.as_raw_fd());
+        let entry =
+            opcode::RenameAt::new(old_fd, old_name.as_ptr(), new_fd, new_name.as_ptr()).build();
+        let buffers = Buffers::Rename {
+            _old_name: old_name,
+            _new_name: new_name,
+        };
+        self.submit((old_dir.clone(), Some(new_dir.clone())), buffers, entry)
+            .await
+            .map(|_| ())
+    }
+}
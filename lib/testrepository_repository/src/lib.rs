@@ -24,9 +24,17 @@
 // license you chose for the specific language governing permissions and
 // limitations under that license.
 
+pub mod encryption;
 pub mod error;
 pub mod file;
 pub mod implementations;
 pub mod io;
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+pub(crate) mod io_uring;
 pub mod memory;
+pub mod partition;
 pub mod repository;
+pub mod result;
+pub mod retention;
+pub mod run;
+pub mod sqlite;
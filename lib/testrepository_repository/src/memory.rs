@@ -14,19 +14,85 @@
 // limitations under that license.
 
 use std::{
+    collections::{HashMap, HashSet},
     fmt::{Debug, Display},
     // Locking data, not IO access - but don't hold the lock across IO operations
     sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
 };
 
 use async_trait::async_trait;
 use tracing::instrument;
 
-use crate::{error::Result, repository::Repository};
+use crate::{
+    error::Result,
+    repository::Repository,
+    result::TestResult,
+    retention::RetentionPolicy,
+    run::RunWriter,
+};
 
 #[derive(Default, Debug)]
 struct MemoryState {
-    runs: Vec<()>,
+    runs: Vec<Vec<TestResult>>,
+    /// The id of `runs[0]`. Pruning drops entries from the front of `runs`
+    /// without renumbering the rest, so a run's id is `oldest + its index`
+    /// rather than its bare `Vec` index — otherwise a stale pre-prune id
+    /// would silently resolve to a different run after pruning, reusing an
+    /// id that should stay retired.
+    oldest: usize,
+}
+
+impl MemoryState {
+    /// Drop the oldest runs that fall outside `policy`, returning how many
+    /// were removed.
+    fn prune(&mut self, policy: &RetentionPolicy, now: SystemTime) -> usize {
+        if policy.is_unlimited() {
+            return 0;
+        }
+        let run_times: Vec<SystemTime> = self.runs.iter().map(|run| newest_time(run)).collect();
+        let count = policy.prune_count(&run_times, now);
+        self.runs.drain(..count);
+        self.oldest += count;
+        count
+    }
+
+    /// The id of the most recent run, if any.
+    fn latest_id(&self) -> Option<usize> {
+        self.runs.len().checked_sub(1).map(|last| self.oldest + last)
+    }
+
+    /// Map a run id to its index in `runs`, accounting for ids retired by
+    /// pruning.
+    fn index_of(&self, id: usize) -> Option<usize> {
+        let index = id.checked_sub(self.oldest)?;
+        (index < self.runs.len()).then_some(index)
+    }
+
+    /// The tests whose most recently observed status is a failure, computed
+    /// by replaying runs newest-to-oldest and keeping the first (i.e. most
+    /// recent) outcome seen for each test id.
+    fn failing(&self) -> Vec<TestResult> {
+        let mut seen = HashSet::new();
+        let mut failing = Vec::new();
+        for run in self.runs.iter().rev() {
+            for result in run {
+                if seen.insert(result.id.clone()) && result.status.is_failure() {
+                    failing.push(result.clone());
+                }
+            }
+        }
+        failing
+    }
+}
+
+/// The newest timestamp observed amongst a run's results, used to decide its
+/// age for retention purposes. An empty run is treated as maximally old.
+fn newest_time(run: &[TestResult]) -> SystemTime {
+    run.iter()
+        .map(|r| r.stop_time)
+        .max()
+        .unwrap_or(std::time::UNIX_EPOCH)
 }
 
 /// Process memory backed store for MemoryRepository.
@@ -56,6 +122,7 @@ impl Debug for MemoryStore {
 pub struct Memory {
     state: Arc<Mutex<MemoryState>>,
     path: String,
+    retention: RetentionPolicy,
 }
 
 impl Display for Memory {
@@ -76,11 +143,18 @@ impl Memory {
             Ok(Self {
                 state: store.repos[path].clone(),
                 path: path.into(),
+                retention: RetentionPolicy::default(),
             })
         } else {
             Err(eyre::eyre!("Repository not found at {}", path))?
         }
     }
+
+    /// Apply a retention policy, pruning old runs as new ones are committed.
+    pub fn with_retention(mut self, retention: RetentionPolicy) -> Self {
+        self.retention = retention;
+        self
+    }
 }
 
 #[async_trait]
@@ -88,4 +162,85 @@ impl Repository for Memory {
     async fn count(&self) -> Result<usize> {
         Ok(self.state.lock().unwrap().runs.len())
     }
+
+    async fn latest_id(&self) -> Result<Option<usize>> {
+        Ok(self.state.lock().unwrap().latest_id())
+    }
+
+    async fn open_run(&self) -> Result<RunWriter> {
+        Ok(RunWriter::Memory(MemoryRunWriter {
+            state: self.state.clone(),
+            retention: self.retention,
+            results: Vec::new(),
+        }))
+    }
+
+    async fn get_run(&self, id: usize) -> Result<Vec<TestResult>> {
+        let state = self.state.lock().unwrap();
+        state
+            .index_of(id)
+            .map(|index| state.runs[index].clone())
+            .ok_or_else(|| eyre::eyre!("No such run {}", id).into())
+    }
+
+    async fn prune(&self) -> Result<usize> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .prune(&self.retention, SystemTime::now()))
+    }
+
+    async fn failing(&self) -> Result<Vec<TestResult>> {
+        Ok(self.state.lock().unwrap().failing())
+    }
+
+    async fn test_times(
+        &self,
+        ids: &[String],
+        default: Duration,
+    ) -> Result<HashMap<String, Duration>> {
+        // The trait default walks `(0..count).rev()` as run ids, which
+        // assumes ids are contiguous from 0; pruning retires the oldest ids
+        // instead of renumbering, so real ids start at `oldest`.
+        let mut times = HashMap::new();
+        let mut remaining: HashSet<&String> = ids.iter().collect();
+        let state = self.state.lock().unwrap();
+        for run in state.runs.iter().rev() {
+            if remaining.is_empty() {
+                break;
+            }
+            for result in run {
+                if remaining.remove(&result.id) {
+                    times.insert(result.id.clone(), result.duration());
+                }
+            }
+        }
+        for id in remaining {
+            times.insert(id.clone(), default);
+        }
+        Ok(times)
+    }
+}
+
+/// Collects the results of a single run before appending them to a
+/// [`MemoryState`] on commit.
+#[derive(Debug)]
+pub struct MemoryRunWriter {
+    state: Arc<Mutex<MemoryState>>,
+    retention: RetentionPolicy,
+    results: Vec<TestResult>,
+}
+
+impl MemoryRunWriter {
+    pub(crate) fn push(&mut self, result: TestResult) {
+        self.results.push(result);
+    }
+
+    pub(crate) async fn commit(self) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.runs.push(self.results);
+        state.prune(&self.retention, SystemTime::now());
+        Ok(())
+    }
 }
@@ -0,0 +1,49 @@
+//! Greedy partitioning of a test list into balanced-cost groups.
+
+// Copyright (c) 2009,2024 Testrepository Contributors
+//
+// Licensed under either the Apache License, Version 2.0 or the BSD 3-clause
+// license at the users choice. A copy of both licenses are available in the
+// project source as Apache-2.0 and BSD. You may not use this file except in
+// compliance with one of these two licences.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under these licenses is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// license you chose for the specific language governing permissions and
+// limitations under that license.
+
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    time::Duration,
+};
+
+/// Split `ids` into `n` groups of roughly equal total duration, so each group
+/// can be handed to a parallel worker with a similar wall-clock cost.
+///
+/// Uses the Longest-Processing-Time (LPT) greedy heuristic: sort tests by
+/// descending duration, then repeatedly assign the next-longest test to
+/// whichever group currently has the smallest running total. A min-heap
+/// keyed by running total keeps this at O(m log n) for `m` tests and `n`
+/// groups. Ids missing from `times` (e.g. never-before-seen tests) are
+/// treated as zero-duration.
+pub fn partition(ids: &[String], times: &HashMap<String, Duration>, n: usize) -> Vec<Vec<String>> {
+    if n == 0 {
+        return Vec::new();
+    }
+    let mut sorted: Vec<&String> = ids.iter().collect();
+    sorted.sort_by_key(|id| Reverse(times.get(*id).copied().unwrap_or_default()));
+
+    let mut groups = vec![Vec::new(); n];
+    let mut totals: BinaryHeap<Reverse<(Duration, usize)>> =
+        (0..n).map(|bin| Reverse((Duration::ZERO, bin))).collect();
+
+    for id in sorted {
+        let Reverse((total, bin)) = totals.pop().expect("one heap entry per group");
+        groups[bin].push(id.clone());
+        let duration = times.get(id).copied().unwrap_or_default();
+        totals.push(Reverse((total + duration, bin)));
+    }
+    groups
+}
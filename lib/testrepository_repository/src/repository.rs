@@ -13,9 +13,14 @@
 // license you chose for the specific language governing permissions and
 // limitations under that license.
 
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
 use async_trait::async_trait;
 
-use crate::error::Result;
+use crate::{error::Result, result::TestResult, run::RunWriter};
 
 /// A repository containing test results.
 #[async_trait]
@@ -32,4 +37,58 @@ pub trait Repository {
             Ok(Some(count - 1))
         }
     }
+
+    /// Open a new run, ready to have test results pushed into it.
+    ///
+    /// Nothing is persisted until [`RunWriter::commit`] is called.
+    async fn open_run(&self) -> Result<RunWriter>;
+
+    /// Get all the test results recorded in the run with the given id.
+    async fn get_run(&self, id: usize) -> Result<Vec<TestResult>>;
+
+    /// Get the tests currently considered failing: the union across all
+    /// runs, computed by replaying outcomes newest-to-oldest so that a later
+    /// `Pass` clears an earlier `Fail`.
+    ///
+    /// Feed the ids of the result back to [`crate::partition::partition`] to
+    /// split a "re-run what's broken" job across parallel workers.
+    async fn failing(&self) -> Result<Vec<TestResult>>;
+
+    /// Prune runs that fall outside this repository's retention policy,
+    /// returning how many were removed.
+    ///
+    /// Runs are also pruned automatically as part of committing a new run,
+    /// so callers only need this to force a cleanup ahead of the next commit
+    /// (e.g. from a scheduled job).
+    async fn prune(&self) -> Result<usize>;
+
+    /// Get the most recently observed duration of each of `ids`, scanning
+    /// runs newest-first and falling back to `default` for any id that was
+    /// never seen.
+    ///
+    /// Feed the result to [`crate::partition::partition`] to split `ids` into
+    /// balanced groups for parallel workers.
+    async fn test_times(
+        &self,
+        ids: &[String],
+        default: Duration,
+    ) -> Result<HashMap<String, Duration>> {
+        let mut times = HashMap::new();
+        let mut remaining: HashSet<&String> = ids.iter().collect();
+        let count = self.count().await?;
+        for run_id in (0..count).rev() {
+            if remaining.is_empty() {
+                break;
+            }
+            for result in self.get_run(run_id).await? {
+                if remaining.remove(&result.id) {
+                    times.insert(result.id.clone(), result.duration());
+                }
+            }
+        }
+        for id in remaining {
+            times.insert(id.clone(), default);
+        }
+        Ok(times)
+    }
 }
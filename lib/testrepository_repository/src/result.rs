@@ -0,0 +1,118 @@
+//! The data model for the outcome of running a single test.
+
+// Copyright (c) 2009,2024 Testrepository Contributors
+//
+// Licensed under either the Apache License, Version 2.0 or the BSD 3-clause
+// license at the users choice. A copy of both licenses are available in the
+// project source as Apache-2.0 and BSD. You may not use this file except in
+// compliance with one of these two licences.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under these licenses is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// license you chose for the specific language governing permissions and
+// limitations under that license.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// The outcome of a single test.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TestStatus {
+    /// The test ran and passed.
+    Pass,
+    /// The test ran and failed.
+    Fail,
+    /// The test was not run.
+    Skip,
+    /// The test failed, as expected.
+    ExpectedFail,
+    /// The test passed, but was expected to fail.
+    UnexpectedSuccess,
+    /// The test could not be run at all (e.g. an import or fixture error).
+    Error,
+}
+
+impl TestStatus {
+    /// True for outcomes that count as "currently broken" for the purposes
+    /// of a [`crate::repository::Repository::failing`] query.
+    pub fn is_failure(&self) -> bool {
+        matches!(self, TestStatus::Fail | TestStatus::Error)
+    }
+}
+
+/// A captured artifact attached to a test result, such as stdout, stderr, or a
+/// traceback.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attachment {
+    /// The MIME type of `data`, e.g. `text/plain` or `text/x-traceback`.
+    pub mime_type: String,
+    /// The raw bytes of the attachment.
+    pub data: Vec<u8>,
+}
+
+/// The outcome of running a single test, as recorded in a run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestResult {
+    /// The id (name) of the test.
+    pub id: String,
+    /// The status the test finished with.
+    pub status: TestStatus,
+    /// When the test started running.
+    #[serde(with = "system_time_secs")]
+    pub start_time: SystemTime,
+    /// When the test finished running.
+    #[serde(with = "system_time_secs")]
+    pub stop_time: SystemTime,
+    /// Free-form tags associated with the test, e.g. `worker-0`.
+    pub tags: BTreeSet<String>,
+    /// Named attachments captured while the test ran.
+    pub attachments: HashMap<String, Attachment>,
+}
+
+impl TestResult {
+    /// How long the test took to run.
+    ///
+    /// Returns a zero duration if `stop_time` is before `start_time`, which
+    /// should not happen in practice but is not worth panicking over.
+    pub fn duration(&self) -> Duration {
+        self.stop_time
+            .duration_since(self.start_time)
+            .unwrap_or_default()
+    }
+}
+
+/// Convert a `SystemTime` to fractional seconds since the Unix epoch.
+///
+/// Used by backends (e.g. the sqlite repository) that store timestamps as a
+/// plain numeric column rather than a native timestamp type.
+pub(crate) fn system_time_to_secs(t: SystemTime) -> f64 {
+    t.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64()
+}
+
+/// Inverse of [`system_time_to_secs`].
+pub(crate) fn system_time_from_secs(secs: f64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs_f64(secs)
+}
+
+/// (De)serialize a `SystemTime` as fractional seconds since the Unix epoch, so
+/// that `TestResult` can round-trip through formats (e.g. JSON) with no native
+/// timestamp type.
+mod system_time_secs {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{system_time_from_secs, system_time_to_secs};
+    use std::time::SystemTime;
+
+    pub fn serialize<S: Serializer>(t: &SystemTime, s: S) -> Result<S::Ok, S::Error> {
+        system_time_to_secs(*t).serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<SystemTime, D::Error> {
+        f64::deserialize(d).map(system_time_from_secs)
+    }
+}
@@ -0,0 +1,65 @@
+//! Retention policy controlling how many runs a repository keeps.
+
+// Copyright (c) 2009,2024 Testrepository Contributors
+//
+// Licensed under either the Apache License, Version 2.0 or the BSD 3-clause
+// license at the users choice. A copy of both licenses are available in the
+// project source as Apache-2.0 and BSD. You may not use this file except in
+// compliance with one of these two licences.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under these licenses is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// license you chose for the specific language governing permissions and
+// limitations under that license.
+
+use std::time::{Duration, SystemTime};
+
+/// How many runs (and for how long) a repository should keep.
+///
+/// An unset field places no bound on that axis; the default policy keeps
+/// everything forever.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RetentionPolicy {
+    max_runs: Option<usize>,
+    max_age: Option<Duration>,
+}
+
+impl RetentionPolicy {
+    /// Keep no more than `max_runs` runs, pruning the oldest first.
+    pub fn with_max_runs(mut self, max_runs: usize) -> Self {
+        self.max_runs = Some(max_runs);
+        self
+    }
+
+    /// Keep no run older than `max_age`.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// True if this policy never prunes anything.
+    pub fn is_unlimited(&self) -> bool {
+        self.max_runs.is_none() && self.max_age.is_none()
+    }
+
+    /// Given the newest-observed timestamp of each stored run, oldest first,
+    /// work out how many of the oldest runs must be pruned to satisfy this
+    /// policy.
+    pub fn prune_count(&self, run_times: &[SystemTime], now: SystemTime) -> usize {
+        let by_count = self
+            .max_runs
+            .map(|max| run_times.len().saturating_sub(max))
+            .unwrap_or(0);
+        let by_age = self
+            .max_age
+            .map(|max_age| {
+                run_times
+                    .iter()
+                    .take_while(|t| now.duration_since(**t).unwrap_or_default() > max_age)
+                    .count()
+            })
+            .unwrap_or(0);
+        by_count.max(by_age)
+    }
+}
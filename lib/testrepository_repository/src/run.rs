@@ -0,0 +1,56 @@
+//! Collecting the results of a single test run before committing it to a
+//! repository.
+
+// Copyright (c) 2009,2024 Testrepository Contributors
+//
+// Licensed under either the Apache License, Version 2.0 or the BSD 3-clause
+// license at the users choice. A copy of both licenses are available in the
+// project source as Apache-2.0 and BSD. You may not use this file except in
+// compliance with one of these two licences.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under these licenses is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// license you chose for the specific language governing permissions and
+// limitations under that license.
+
+use crate::{
+    error::Result, file::FileRunWriter, memory::MemoryRunWriter, result::TestResult,
+    sqlite::SqliteRunWriter,
+};
+
+/// A handle for collecting the results of a single test run before committing
+/// them to the [`crate::repository::Repository`] that produced it.
+///
+/// Push individual [`TestResult`]s with [`RunWriter::push`] as they become
+/// available, then call [`RunWriter::commit`] once to persist the whole run.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum RunWriter {
+    /// Run writer backing a [`crate::memory::Memory`] repository.
+    Memory(MemoryRunWriter),
+    /// Run writer backing a [`crate::file::File`] repository.
+    File(FileRunWriter),
+    /// Run writer backing a [`crate::sqlite::Sqlite`] repository.
+    Sqlite(SqliteRunWriter),
+}
+
+impl RunWriter {
+    /// Record a single test outcome as part of this run.
+    pub fn push(&mut self, result: TestResult) {
+        match self {
+            RunWriter::Memory(w) => w.push(result),
+            RunWriter::File(w) => w.push(result),
+            RunWriter::Sqlite(w) => w.push(result),
+        }
+    }
+
+    /// Commit the collected results to the repository as a new run.
+    pub async fn commit(self) -> Result<()> {
+        match self {
+            RunWriter::Memory(w) => w.commit().await,
+            RunWriter::File(w) => w.commit().await,
+            RunWriter::Sqlite(w) => w.commit().await,
+        }
+    }
+}
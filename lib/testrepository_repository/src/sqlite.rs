@@ -0,0 +1,335 @@
+//! SQLite backed repository, addressed via `sqlite://` URLs.
+
+// Copyright (c) 2009,2024 Testrepository Contributors
+//
+// Licensed under either the Apache License, Version 2.0 or the BSD 3-clause
+// license at the users choice. A copy of both licenses are available in the
+// project source as Apache-2.0 and BSD. You may not use this file except in
+// compliance with one of these two licences.
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under these licenses is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.  See the
+// license you chose for the specific language governing permissions and
+// limitations under that license.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use async_trait::async_trait;
+use sqlx::{sqlite::SqlitePoolOptions, Row, SqlitePool};
+use tracing::instrument;
+
+use crate::{
+    error::{Eyrify, Result},
+    repository::Repository,
+    result::{Attachment, TestResult, TestStatus},
+    retention::RetentionPolicy,
+    run::RunWriter,
+};
+
+pub static FORMAT: &str = "1";
+
+/// SQLite repository. Keeps a `runs` table (one row per run) and a
+/// `test_results` table (one row per test result, keyed by run id), which
+/// makes [`Repository::count`], [`Repository::latest_id`] and per-test
+/// queries cheap index lookups instead of file parsing.
+#[derive(Debug)]
+pub struct Sqlite {
+    pool: SqlitePool,
+    retention: RetentionPolicy,
+}
+
+impl Sqlite {
+    /// Open (creating if necessary) a SQLite repository at `path`.
+    #[instrument(ret, err)]
+    pub async fn new(path: &Path) -> Result<Self> {
+        let url = format!("sqlite://{}?mode=rwc", path.display());
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect(&url)
+            .await
+            .eyre()?;
+        Self::migrate(&pool).await?;
+        Ok(Self {
+            pool,
+            retention: RetentionPolicy::default(),
+        })
+    }
+
+    /// Apply a retention policy, pruning old runs as new ones are committed.
+    pub fn with_retention(mut self, retention: RetentionPolicy) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    async fn migrate(pool: &SqlitePool) -> Result<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS format (
+                 version TEXT NOT NULL
+             )",
+        )
+        .execute(pool)
+        .await
+        .eyre()?;
+        let format_rows = sqlx::query("SELECT COUNT(*) AS n FROM format")
+            .fetch_one(pool)
+            .await
+            .eyre()?;
+        if format_rows.get::<i64, _>("n") == 0 {
+            sqlx::query("INSERT INTO format (version) VALUES (?1)")
+                .bind(FORMAT)
+                .execute(pool)
+                .await
+                .eyre()?;
+        }
+        // Not AUTOINCREMENT: that starts ids at 1, but `Memory`/`File` both
+        // start ids at 0 (the first stream file is `0`, the first `Vec`
+        // index is `0`), and callers like `get_run`/`latest_id` rely on ids
+        // lining up with `count()`. `commit()` below assigns ids explicitly
+        // to keep sqlite 0-based too.
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS runs (
+                 id INTEGER PRIMARY KEY
+             )",
+        )
+        .execute(pool)
+        .await
+        .eyre()?;
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS test_results (
+                 run_id INTEGER NOT NULL REFERENCES runs(id),
+                 test_id TEXT NOT NULL,
+                 status TEXT NOT NULL,
+                 start_time REAL NOT NULL,
+                 stop_time REAL NOT NULL,
+                 tags TEXT NOT NULL,
+                 attachments TEXT NOT NULL
+             )",
+        )
+        .execute(pool)
+        .await
+        .eyre()?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS test_results_run_id ON test_results(run_id)")
+            .execute(pool)
+            .await
+            .eyre()?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Repository for Sqlite {
+    async fn count(&self) -> Result<usize> {
+        let row = sqlx::query("SELECT COUNT(*) AS n FROM runs")
+            .fetch_one(&self.pool)
+            .await
+            .eyre()?;
+        Ok(row.get::<i64, _>("n") as usize)
+    }
+
+    async fn latest_id(&self) -> Result<Option<usize>> {
+        let row = sqlx::query("SELECT MAX(id) AS max_id FROM runs")
+            .fetch_one(&self.pool)
+            .await
+            .eyre()?;
+        let max_id: Option<i64> = row.get("max_id");
+        Ok(max_id.map(|id| id as usize))
+    }
+
+    async fn open_run(&self) -> Result<RunWriter> {
+        Ok(RunWriter::Sqlite(SqliteRunWriter {
+            pool: self.pool.clone(),
+            retention: self.retention,
+            results: Vec::new(),
+        }))
+    }
+
+    async fn get_run(&self, id: usize) -> Result<Vec<TestResult>> {
+        // A run with no results still has a `runs` row, so it is the absence
+        // of that row (never committed, or pruned) that marks a missing run.
+        let run = sqlx::query("SELECT id FROM runs WHERE id = ?1")
+            .bind(id as i64)
+            .fetch_optional(&self.pool)
+            .await
+            .eyre()?;
+        if run.is_none() {
+            Err(eyre::eyre!("No such run {}", id))?
+        }
+        let rows = sqlx::query(
+            "SELECT test_id, status, start_time, stop_time, tags, attachments
+             FROM test_results WHERE run_id = ?1",
+        )
+        .bind(id as i64)
+        .fetch_all(&self.pool)
+        .await
+        .eyre()?;
+        rows.into_iter().map(row_to_result).collect()
+    }
+
+    async fn prune(&self) -> Result<usize> {
+        prune(&self.pool, &self.retention, SystemTime::now()).await
+    }
+
+    async fn failing(&self) -> Result<Vec<TestResult>> {
+        // For each test id, take its result from the most recent run it
+        // appeared in, then keep only the failing ones: a later `Pass`
+        // clears an earlier `Fail` without needing a separate index.
+        let rows = sqlx::query(
+            "SELECT test_id, status, start_time, stop_time, tags, attachments
+             FROM test_results t1
+             WHERE run_id = (
+                 SELECT MAX(t2.run_id) FROM test_results t2 WHERE t2.test_id = t1.test_id
+             )",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .eyre()?;
+        let results: Vec<TestResult> = rows.into_iter().map(row_to_result).collect::<Result<_>>()?;
+        Ok(results.into_iter().filter(|r| r.status.is_failure()).collect())
+    }
+
+    async fn test_times(
+        &self,
+        ids: &[String],
+        default: Duration,
+    ) -> Result<HashMap<String, Duration>> {
+        // The trait default walks `(0..count).rev()`, which assumes ids are
+        // contiguous from 0; pruning (see `prune` above) deletes rows by id
+        // rather than renumbering, so the surviving ids can have gaps. Walk
+        // the real ids, newest first, instead.
+        let mut times = HashMap::new();
+        let mut remaining: HashSet<&String> = ids.iter().collect();
+        let rows = sqlx::query("SELECT id FROM runs ORDER BY id DESC")
+            .fetch_all(&self.pool)
+            .await
+            .eyre()?;
+        for row in rows {
+            if remaining.is_empty() {
+                break;
+            }
+            let run_id: i64 = row.get("id");
+            for result in self.get_run(run_id as usize).await? {
+                if remaining.remove(&result.id) {
+                    times.insert(result.id.clone(), result.duration());
+                }
+            }
+        }
+        for id in remaining {
+            times.insert(id.clone(), default);
+        }
+        Ok(times)
+    }
+}
+
+/// Prune runs that fall outside `policy`, deleting their rows from both
+/// `runs` and `test_results`.
+async fn prune(pool: &SqlitePool, policy: &RetentionPolicy, now: SystemTime) -> Result<usize> {
+    if policy.is_unlimited() {
+        return Ok(0);
+    }
+    let rows = sqlx::query(
+        "SELECT r.id AS id, COALESCE(MAX(t.stop_time), 0) AS newest
+         FROM runs r LEFT JOIN test_results t ON t.run_id = r.id
+         GROUP BY r.id ORDER BY r.id ASC",
+    )
+    .fetch_all(pool)
+    .await
+    .eyre()?;
+    let ids: Vec<i64> = rows.iter().map(|r| r.get("id")).collect();
+    let times: Vec<SystemTime> = rows
+        .iter()
+        .map(|r| crate::result::system_time_from_secs(r.get("newest")))
+        .collect();
+    let prune_n = policy.prune_count(&times, now);
+    if prune_n == 0 {
+        return Ok(0);
+    }
+    let mut tx = pool.begin().await.eyre()?;
+    for id in &ids[..prune_n] {
+        sqlx::query("DELETE FROM test_results WHERE run_id = ?1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .eyre()?;
+        sqlx::query("DELETE FROM runs WHERE id = ?1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .eyre()?;
+    }
+    tx.commit().await.eyre()?;
+    Ok(prune_n)
+}
+
+fn row_to_result(row: sqlx::sqlite::SqliteRow) -> Result<TestResult> {
+    let status: String = row.get("status");
+    let tags: String = row.get("tags");
+    let attachments: String = row.get("attachments");
+    Ok(TestResult {
+        id: row.get("test_id"),
+        status: serde_json::from_str::<TestStatus>(&status).eyre()?,
+        start_time: crate::result::system_time_from_secs(row.get("start_time")),
+        stop_time: crate::result::system_time_from_secs(row.get("stop_time")),
+        tags: serde_json::from_str(&tags).eyre()?,
+        attachments: serde_json::from_str::<std::collections::HashMap<String, Attachment>>(
+            &attachments,
+        )
+        .eyre()?,
+    })
+}
+
+/// Collects the results of a single run before inserting them into the
+/// `runs`/`test_results` tables on commit.
+#[derive(Debug)]
+pub struct SqliteRunWriter {
+    pool: SqlitePool,
+    retention: RetentionPolicy,
+    results: Vec<TestResult>,
+}
+
+impl SqliteRunWriter {
+    pub(crate) fn push(&mut self, result: TestResult) {
+        self.results.push(result);
+    }
+
+    pub(crate) async fn commit(self) -> Result<()> {
+        let mut tx = self.pool.begin().await.eyre()?;
+        let run_id: i64 = sqlx::query("SELECT COALESCE(MAX(id), -1) + 1 AS next_id FROM runs")
+            .fetch_one(&mut *tx)
+            .await
+            .eyre()?
+            .get("next_id");
+        sqlx::query("INSERT INTO runs (id) VALUES (?1)")
+            .bind(run_id)
+            .execute(&mut *tx)
+            .await
+            .eyre()?;
+        for result in &self.results {
+            let tags = serde_json::to_string(&result.tags).eyre()?;
+            let attachments = serde_json::to_string(&result.attachments).eyre()?;
+            let status = serde_json::to_string(&result.status).eyre()?;
+            sqlx::query(
+                "INSERT INTO test_results
+                     (run_id, test_id, status, start_time, stop_time, tags, attachments)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )
+            .bind(run_id)
+            .bind(&result.id)
+            .bind(status)
+            .bind(crate::result::system_time_to_secs(result.start_time))
+            .bind(crate::result::system_time_to_secs(result.stop_time))
+            .bind(tags)
+            .bind(attachments)
+            .execute(&mut *tx)
+            .await
+            .eyre()?;
+        }
+        tx.commit().await.eyre()?;
+        prune(&self.pool, &self.retention, SystemTime::now()).await?;
+        Ok(())
+    }
+}
@@ -6,7 +6,7 @@ use testrepository_repository::repository::Repository as _;
 use super::Implementation;
 
 #[test_matrix(
-        [Implementation::Memory, Implementation::RepoV1, Implementation::RepoV2]
+        [Implementation::Memory, Implementation::RepoV1, Implementation::RepoV2, Implementation::Sqlite]
     )]
 #[tokio::test]
 #[traced_test]
@@ -0,0 +1,84 @@
+use std::time::SystemTime;
+
+use tracing_test::traced_test;
+use url::Url;
+
+use testrepository_repository::{
+    encryption::Cipher,
+    file::File,
+    implementations::{OpenOptions, Repository},
+    repository::Repository as _,
+    result::{TestResult, TestStatus},
+};
+
+fn sample_result() -> TestResult {
+    let now = SystemTime::now();
+    TestResult {
+        id: "pkg::test_thing".into(),
+        status: TestStatus::Pass,
+        start_time: now,
+        stop_time: now,
+        tags: Default::default(),
+        attachments: Default::default(),
+    }
+}
+
+#[tokio::test]
+#[traced_test]
+async fn encrypted_repository_round_trips_with_the_right_password() {
+    let dir = tempfile::tempdir().unwrap();
+    File::initialize_v2_with_encryption(dir.path(), Cipher::Aes256Gcm, "hunter2")
+        .await
+        .unwrap();
+    let url = Url::from_file_path(dir.path()).unwrap();
+
+    let opts = OpenOptions::default().with_encryption("hunter2", Cipher::Aes256Gcm);
+    let repo = Repository::open_with(&url, opts).await.unwrap();
+    let mut writer = repo.open_run().await.unwrap();
+    writer.push(sample_result());
+    writer.commit().await.unwrap();
+
+    let opts = OpenOptions::default().with_encryption("hunter2", Cipher::Aes256Gcm);
+    let repo = Repository::open_with(&url, opts).await.unwrap();
+    let results = repo.get_run(0).await.unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].id, "pkg::test_thing");
+    assert_eq!(results[0].status, TestStatus::Pass);
+}
+
+#[tokio::test]
+#[traced_test]
+async fn opening_an_encrypted_repository_without_a_password_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    File::initialize_v2_with_encryption(dir.path(), Cipher::Aes256Gcm, "hunter2")
+        .await
+        .unwrap();
+    let url = Url::from_file_path(dir.path()).unwrap();
+
+    let e = Repository::open(&url).await.unwrap_err();
+    assert!(
+        e.to_string().contains("password is required"),
+        "bad error {}",
+        e
+    );
+}
+
+#[tokio::test]
+#[traced_test]
+async fn reading_a_run_with_the_wrong_password_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    File::initialize_v2_with_encryption(dir.path(), Cipher::Aes256Gcm, "hunter2")
+        .await
+        .unwrap();
+    let url = Url::from_file_path(dir.path()).unwrap();
+
+    let opts = OpenOptions::default().with_encryption("hunter2", Cipher::Aes256Gcm);
+    let repo = Repository::open_with(&url, opts).await.unwrap();
+    let mut writer = repo.open_run().await.unwrap();
+    writer.push(sample_result());
+    writer.commit().await.unwrap();
+
+    let opts = OpenOptions::default().with_encryption("wrong", Cipher::Aes256Gcm);
+    let repo = Repository::open_with(&url, opts).await.unwrap();
+    assert!(repo.get_run(0).await.is_err());
+}
@@ -0,0 +1,58 @@
+use std::time::SystemTime;
+
+use test_case::test_matrix;
+use tracing_test::traced_test;
+
+use testrepository_repository::{
+    repository::Repository as _,
+    result::{TestResult, TestStatus},
+};
+
+use super::Implementation;
+
+fn result(id: &str, status: TestStatus) -> TestResult {
+    let now = SystemTime::now();
+    TestResult {
+        id: id.into(),
+        status,
+        start_time: now,
+        stop_time: now,
+        tags: Default::default(),
+        attachments: Default::default(),
+    }
+}
+
+#[test_matrix([Implementation::Memory, Implementation::RepoV2, Implementation::Sqlite])]
+#[tokio::test]
+#[traced_test]
+async fn a_later_pass_clears_an_earlier_fail(implementation: Implementation) {
+    let guard = implementation.setup().await;
+    let repo = guard.open().await;
+
+    let mut writer = repo.open_run().await.unwrap();
+    writer.push(result("pkg::flaky", TestStatus::Fail));
+    writer.push(result("pkg::broken", TestStatus::Fail));
+    writer.commit().await.unwrap();
+
+    let mut writer = repo.open_run().await.unwrap();
+    writer.push(result("pkg::flaky", TestStatus::Pass));
+    writer.commit().await.unwrap();
+
+    let failing: Vec<String> = repo
+        .failing()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|r| r.id)
+        .collect();
+    assert_eq!(failing, vec!["pkg::broken".to_string()]);
+}
+
+#[tokio::test]
+#[traced_test]
+async fn v1_repositories_do_not_support_failing() {
+    let guard = Implementation::RepoV1.setup().await;
+    let repo = guard.open().await;
+
+    assert!(repo.failing().await.is_err());
+}
@@ -0,0 +1,50 @@
+use std::time::SystemTime;
+
+use test_case::test_matrix;
+use tracing_test::traced_test;
+
+use testrepository_repository::{
+    repository::Repository as _,
+    result::{TestResult, TestStatus},
+};
+
+use super::Implementation;
+
+fn sample_result() -> TestResult {
+    let now = SystemTime::now();
+    TestResult {
+        id: "pkg::test_thing".into(),
+        status: TestStatus::Pass,
+        start_time: now,
+        stop_time: now,
+        tags: Default::default(),
+        attachments: Default::default(),
+    }
+}
+
+#[test_matrix(
+        [Implementation::Memory, Implementation::RepoV1, Implementation::RepoV2, Implementation::Sqlite]
+    )]
+#[tokio::test]
+#[traced_test]
+async fn latest_id_empty_repo(implementation: Implementation) {
+    let guard = implementation.setup().await;
+    let repo = guard.open().await;
+    assert_eq!(repo.latest_id().await.unwrap(), None);
+}
+
+#[test_matrix([Implementation::Memory, Implementation::RepoV2, Implementation::Sqlite])]
+#[tokio::test]
+#[traced_test]
+async fn latest_id_is_the_most_recently_committed_run(implementation: Implementation) {
+    let guard = implementation.setup().await;
+    let repo = guard.open().await;
+
+    for _ in 0..3 {
+        let mut writer = repo.open_run().await.unwrap();
+        writer.push(sample_result());
+        writer.commit().await.unwrap();
+    }
+
+    assert_eq!(repo.latest_id().await.unwrap(), Some(2));
+}
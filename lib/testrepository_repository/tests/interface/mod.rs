@@ -7,12 +7,19 @@ use testrepository_repository::{
 use url::Url;
 
 mod count;
+mod encryption;
+mod failing;
 mod latest_id;
+mod partition;
+mod retention;
+mod run;
+mod test_times;
 
 enum Implementation {
     Memory,
     RepoV1,
     RepoV2,
+    Sqlite,
 }
 
 impl Implementation {
@@ -35,6 +42,10 @@ impl Implementation {
                 File::initialize_v2(dir.path()).await.unwrap();
                 TestGuard::RepoV2(dir)
             }
+            Implementation::Sqlite => {
+                let dir = tempdir().unwrap();
+                TestGuard::Sqlite(dir)
+            }
         }
     }
 }
@@ -43,6 +54,7 @@ enum TestGuard {
     Memory(MemoryStore),
     RepoV1(TempDir),
     RepoV2(TempDir),
+    Sqlite(TempDir),
 }
 
 impl TestGuard {
@@ -62,6 +74,12 @@ impl TestGuard {
                 let url = Url::from_file_path(dir.path()).unwrap();
                 Repository::open(&url).await.unwrap()
             }
+            TestGuard::Sqlite(dir) => {
+                let url =
+                    Url::parse(&format!("sqlite://{}", dir.path().join("repo.db").display()))
+                        .unwrap();
+                Repository::open(&url).await.unwrap()
+            }
         }
     }
 }
@@ -0,0 +1,52 @@
+use std::{collections::HashMap, time::Duration};
+
+use testrepository_repository::partition::partition;
+
+#[test]
+fn balances_groups_by_longest_processing_time() {
+    let ids: Vec<String> = vec!["a", "b", "c", "d"]
+        .into_iter()
+        .map(String::from)
+        .collect();
+    let times: HashMap<String, Duration> = [
+        ("a", 5),
+        ("b", 4),
+        ("c", 3),
+        ("d", 2),
+    ]
+    .into_iter()
+    .map(|(id, secs)| (id.to_string(), Duration::from_secs(secs)))
+    .collect();
+
+    let groups = partition(&ids, &times, 2);
+
+    assert_eq!(groups.len(), 2);
+    let totals: Vec<Duration> = groups
+        .iter()
+        .map(|g| g.iter().map(|id| times[id]).sum())
+        .collect();
+    assert_eq!(totals[0], Duration::from_secs(7));
+    assert_eq!(totals[1], Duration::from_secs(7));
+}
+
+#[test]
+fn unseen_ids_default_to_zero_duration() {
+    let ids: Vec<String> = vec!["known", "unknown"]
+        .into_iter()
+        .map(String::from)
+        .collect();
+    let mut times = HashMap::new();
+    times.insert("known".to_string(), Duration::from_secs(10));
+
+    let groups = partition(&ids, &times, 2);
+
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups.iter().map(Vec::len).sum::<usize>(), 2);
+}
+
+#[test]
+fn zero_groups_returns_empty() {
+    let ids = vec!["a".to_string()];
+    let times = HashMap::new();
+    assert_eq!(partition(&ids, &times, 0), Vec::<Vec<String>>::new());
+}
@@ -0,0 +1,102 @@
+use std::time::SystemTime;
+
+use tracing_test::traced_test;
+use url::Url;
+
+use testrepository_repository::{
+    file::File,
+    implementations::{OpenOptions, Repository},
+    memory::MemoryStore,
+    repository::Repository as _,
+    result::{TestResult, TestStatus},
+};
+
+fn sample_result() -> TestResult {
+    let now = SystemTime::now();
+    TestResult {
+        id: "pkg::test_thing".into(),
+        status: TestStatus::Pass,
+        start_time: now,
+        stop_time: now,
+        tags: Default::default(),
+        attachments: Default::default(),
+    }
+}
+
+async fn commit_run(repo: &Repository) {
+    let mut writer = repo.open_run().await.unwrap();
+    writer.push(sample_result());
+    writer.commit().await.unwrap();
+}
+
+#[tokio::test]
+#[traced_test]
+async fn memory_prunes_to_max_runs_on_commit() {
+    let mut store = MemoryStore::default();
+    store.initialize("a");
+    let opts = OpenOptions::default()
+        .with_memory_store(&store)
+        .with_max_runs(2);
+    let repo = Repository::open_with(&Url::parse("memory://a").unwrap(), opts)
+        .await
+        .unwrap();
+
+    for _ in 0..3 {
+        commit_run(&repo).await;
+    }
+
+    assert_eq!(repo.count().await.unwrap(), 2);
+    // The oldest run's id was reused by neither of the retained runs.
+    assert!(repo.get_run(0).await.is_err());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn file_prunes_to_max_runs_on_commit() {
+    let dir = tempfile::tempdir().unwrap();
+    File::initialize_v2(dir.path()).await.unwrap();
+    let url = Url::from_file_path(dir.path()).unwrap();
+    let opts = OpenOptions::default().with_max_runs(2);
+    let repo = Repository::open_with(&url, opts).await.unwrap();
+
+    for _ in 0..3 {
+        commit_run(&repo).await;
+    }
+
+    assert_eq!(repo.count().await.unwrap(), 2);
+    // The oldest run's id was reused by neither of the retained runs.
+    assert!(repo.get_run(0).await.is_err());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn sqlite_prunes_to_max_runs_on_commit() {
+    let dir = tempfile::tempdir().unwrap();
+    let url = Url::parse(&format!("sqlite://{}", dir.path().join("repo.db").display())).unwrap();
+    let opts = OpenOptions::default().with_max_runs(2);
+    let repo = Repository::open_with(&url, opts).await.unwrap();
+
+    for _ in 0..3 {
+        commit_run(&repo).await;
+    }
+
+    assert_eq!(repo.count().await.unwrap(), 2);
+    // The oldest run's id was reused by neither of the retained runs.
+    assert!(repo.get_run(0).await.is_err());
+}
+
+#[tokio::test]
+#[traced_test]
+async fn explicit_prune_reports_how_many_runs_were_removed() {
+    let mut store = MemoryStore::default();
+    store.initialize("a");
+    let opts = OpenOptions::default().with_memory_store(&store);
+    let repo = Repository::open_with(&Url::parse("memory://a").unwrap(), opts)
+        .await
+        .unwrap();
+
+    for _ in 0..3 {
+        commit_run(&repo).await;
+    }
+    assert_eq!(repo.prune().await.unwrap(), 0);
+}
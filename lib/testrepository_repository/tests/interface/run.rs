@@ -0,0 +1,51 @@
+use std::time::SystemTime;
+
+use test_case::test_matrix;
+use tracing_test::traced_test;
+
+use testrepository_repository::{
+    repository::Repository as _,
+    result::{TestResult, TestStatus},
+};
+
+use super::Implementation;
+
+fn sample_result() -> TestResult {
+    let now = SystemTime::now();
+    TestResult {
+        id: "pkg::test_thing".into(),
+        status: TestStatus::Pass,
+        start_time: now,
+        stop_time: now,
+        tags: Default::default(),
+        attachments: Default::default(),
+    }
+}
+
+#[test_matrix([Implementation::Memory, Implementation::RepoV2, Implementation::Sqlite])]
+#[tokio::test]
+#[traced_test]
+async fn open_run_commit_and_get_run(implementation: Implementation) {
+    let guard = implementation.setup().await;
+    let repo = guard.open().await;
+
+    let mut writer = repo.open_run().await.unwrap();
+    writer.push(sample_result());
+    writer.commit().await.unwrap();
+
+    assert_eq!(repo.count().await.unwrap(), 1);
+    let results = repo.get_run(0).await.unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].id, "pkg::test_thing");
+    assert_eq!(results[0].status, TestStatus::Pass);
+}
+
+#[tokio::test]
+#[traced_test]
+async fn v1_repositories_do_not_support_ingestion() {
+    let guard = Implementation::RepoV1.setup().await;
+    let repo = guard.open().await;
+
+    assert!(repo.open_run().await.is_err());
+    assert!(repo.get_run(0).await.is_err());
+}
@@ -0,0 +1,50 @@
+use std::time::{Duration, SystemTime};
+
+use test_case::test_matrix;
+use tracing_test::traced_test;
+
+use testrepository_repository::{
+    repository::Repository as _,
+    result::{TestResult, TestStatus},
+};
+
+use super::Implementation;
+
+fn result_with_duration(id: &str, duration: Duration) -> TestResult {
+    let start = SystemTime::UNIX_EPOCH;
+    TestResult {
+        id: id.into(),
+        status: TestStatus::Pass,
+        start_time: start,
+        stop_time: start + duration,
+        tags: Default::default(),
+        attachments: Default::default(),
+    }
+}
+
+#[test_matrix([Implementation::Memory, Implementation::RepoV2, Implementation::Sqlite])]
+#[tokio::test]
+#[traced_test]
+async fn test_times_returns_most_recent_duration_and_default_for_unseen(
+    implementation: Implementation,
+) {
+    let guard = implementation.setup().await;
+    let repo = guard.open().await;
+
+    let mut writer = repo.open_run().await.unwrap();
+    writer.push(result_with_duration("pkg::slow", Duration::from_secs(1)));
+    writer.commit().await.unwrap();
+
+    let mut writer = repo.open_run().await.unwrap();
+    writer.push(result_with_duration("pkg::slow", Duration::from_secs(5)));
+    writer.commit().await.unwrap();
+
+    let ids = vec!["pkg::slow".to_string(), "pkg::never_run".to_string()];
+    let times = repo
+        .test_times(&ids, Duration::from_millis(250))
+        .await
+        .unwrap();
+
+    assert_eq!(times["pkg::slow"], Duration::from_secs(5));
+    assert_eq!(times["pkg::never_run"], Duration::from_millis(250));
+}